@@ -0,0 +1,441 @@
+use crate::{lexer::{Token, tokens::TokenKind}, parser::{syntax_tree, cst::{SyntaxElement, SyntaxKind, SyntaxNode}, errors::Error, expressions::Operator}};
+
+/// formats `source` in the one style every Almond file should be in, keeping its comments
+/// a file with a syntax error is left alone, with the error returned
+/// a comment inside of a statement stays next to what it was written next to, and a line comment moves to the end of its line
+pub fn format(source: &str) -> Result<String, Error> {
+	let (mut tree, errors) = syntax_tree(source);
+
+	// everything else still parses, like an import of a module that can't be found from here
+	if let Some(e) = errors.into_iter().find(|e| matches!(e, Error::Syntax(_) | Error::Lex(_))) {
+		return Err(e);
+	}
+
+	for e in &mut tree.children {
+		if let SyntaxElement::Node(e) = e {
+			attach_comments(e);
+		}
+	}
+
+	let mut lines: Vec<String> = Vec::new();
+	// whether nothing but spaces has come since the last statement, so a comment goes on the end of its line
+	let mut after_statement = false;
+	let mut blank_line = false;
+
+	for e in &tree.children {
+		let line = match e {
+			SyntaxElement::Node(e) => {
+				after_statement = true;
+				statement(e)
+			},
+			SyntaxElement::Token(e) => match e.kind {
+				TokenKind::Whitespace => {
+					let newlines = e.slice.matches('\n').count();
+
+					after_statement &= newlines == 0;
+					blank_line |= newlines > 1;
+
+					continue;
+				},
+				TokenKind::Comment | TokenKind::BlockComment | TokenKind::DocComment => match (after_statement, lines.last_mut()) {
+					(true, Some(last)) => {
+						last.push(' ');
+						last.push_str(e.slice);
+
+						continue;
+					},
+					_ => e.slice.trim_end().to_owned(),
+				},
+				// empty statements are left out
+				_ => continue,
+			},
+		};
+
+		// at most one blank line is kept between statements, and none at the start
+		if blank_line && !lines.is_empty() {
+			lines.push(String::new());
+		}
+
+		blank_line = false;
+		lines.push(line);
+	}
+
+	match lines.is_empty() {
+		true => Ok(String::new()),
+		false => Ok(lines.join("\n") + "\n"),
+	}
+}
+
+/// a statement, always ending with a `;`, then any line comments from inside of it
+fn statement(node: &SyntaxNode) -> String {
+	let mut comments: Vec<String> = Vec::new();
+	let mut out = join(node, 0, &mut comments);
+
+	if node.last_token().map(|e| e.kind) != Some(TokenKind::End) {
+		out.push(';');
+	}
+
+	end_line(out, comments)
+}
+
+/// `line` with the line comments from inside of it at the end
+fn end_line(mut line: String, comments: Vec<String>) -> String {
+	for e in comments {
+		line.push(' ');
+		line.push_str(&e);
+	}
+
+	line
+}
+
+fn is_comment(kind: TokenKind) -> bool {
+	kind.is_trivia() && kind != TokenKind::Whitespace
+}
+
+fn is_trivia(e: &SyntaxElement) -> bool {
+	matches!(e, SyntaxElement::Token(e) if e.kind.is_trivia())
+}
+
+/// moves each comment in `node` into the node next to it, so it's written along with that node
+/// a comment goes with the node after it, or with the one before it if a token comes first, like the `;` in `1 /* c */;`
+fn attach_comments(node: &mut SyntaxNode) {
+	let children = std::mem::take(&mut node.children);
+	let significant = |e: &SyntaxElement| !is_trivia(e);
+	let is_node = |e: Option<&SyntaxElement>| matches!(e, Some(SyntaxElement::Node(_)));
+
+	let targets: Vec<Option<(usize, bool)>> = (0..children.len())
+		.map(|i| {
+			if !matches!(&children[i], SyntaxElement::Token(e) if is_comment(e.kind)) {
+				return None;
+			}
+
+			// parentheses are left out when formatting, so their comments go with the expression in them
+			if node.kind == SyntaxKind::Paren {
+				return children.iter().position(|e| is_node(Some(e))).map(|j| (j, i < j));
+			}
+
+			let next = children[i + 1..].iter().position(significant).map(|j| i + 1 + j);
+			let previous = children[..i].iter().rposition(significant);
+
+			match (next, previous) {
+				(Some(j), _) if is_node(children.get(j)) => Some((j, true)),
+				(_, Some(j)) if is_node(children.get(j)) => Some((j, false)),
+				_ => None,
+			}
+		})
+		.collect();
+
+	let mut leading: Vec<Vec<SyntaxElement>> = vec![Vec::new(); children.len()];
+	let mut trailing: Vec<Vec<SyntaxElement>> = vec![Vec::new(); children.len()];
+	let mut kept: Vec<(usize, SyntaxElement)> = Vec::new();
+
+	for (i, e) in children.into_iter().enumerate() {
+		match targets[i] {
+			Some((j, true)) => leading[j].push(e),
+			Some((j, false)) => trailing[j].push(e),
+			None => kept.push((i, e)),
+		}
+	}
+
+	node.children = kept.into_iter()
+		.map(|(i, e)| match e {
+			SyntaxElement::Node(mut e) => {
+				let mut children = std::mem::take(&mut leading[i]);
+				children.append(&mut e.children);
+				children.append(&mut trailing[i]);
+				e.children = children;
+
+				attach_comments(&mut e);
+				SyntaxElement::Node(e)
+			},
+			e => e,
+		})
+		.collect();
+}
+
+/// `text` with the comments directly inside of `node` around it, where line comments are left in `comments` for the end of the line
+fn commented(node: &SyntaxNode, text: String, comments: &mut Vec<String>) -> String {
+	let first = node.children.iter().position(|e| !is_trivia(e)).unwrap_or(node.children.len());
+	let mut out = String::new();
+	let mut after = String::new();
+
+	for (i, e) in node.children.iter().enumerate() {
+		let e = match e {
+			SyntaxElement::Token(e) if is_comment(e.kind) => e,
+			_ => continue,
+		};
+
+		match (e.kind, i < first) {
+			(TokenKind::BlockComment, true) => {
+				out.push_str(e.slice);
+				out.push(' ');
+			},
+			(TokenKind::BlockComment, false) => {
+				after.push(' ');
+				after.push_str(e.slice);
+			},
+			_ => comments.push(e.slice.trim_end().to_owned()),
+		}
+	}
+
+	out + &text + &after
+}
+
+/// the parts of a statement, or of an input or type in one, with a space between them wherever one goes
+fn join(node: &SyntaxNode, indent: usize, comments: &mut Vec<String>) -> String {
+	let mut out = String::new();
+	let mut last: Option<TokenKind> = None;
+
+	for e in &node.children {
+		let (text, first, end) = match e {
+			SyntaxElement::Token(e) if e.kind == TokenKind::BlockComment => (e.slice.to_owned(), e.kind, e.kind),
+			SyntaxElement::Token(e) if is_comment(e.kind) => {
+				comments.push(e.slice.trim_end().to_owned());
+				continue;
+			},
+			SyntaxElement::Token(e) if e.kind.is_trivia() => continue,
+			SyntaxElement::Token(e) => (e.slice.to_owned(), e.kind, e.kind),
+			SyntaxElement::Node(e) => {
+				let text = match e.kind {
+					SyntaxKind::File
+					| SyntaxKind::Binding
+					| SyntaxKind::Import
+					| SyntaxKind::Input
+					| SyntaxKind::Type
+					| SyntaxKind::Constraint
+					| SyntaxKind::ModuleImport
+					| SyntaxKind::FromImport
+					| SyntaxKind::Export
+					| SyntaxKind::Error => join(e, indent, comments),
+					_ => expr(e, indent, comments),
+				};
+
+				let first = e.first_token().map_or(TokenKind::EOF, |e| e.kind);
+				let end = e.last_token().map_or(TokenKind::EOF, |e| e.kind);
+
+				(text, first, end)
+			},
+		};
+
+		if let Some(last) = last {
+			if spaced(last, first) {
+				out.push(' ');
+			}
+		}
+
+		out.push_str(&text);
+		last = Some(end);
+	}
+
+	out
+}
+
+/// whether there's a space between `before` and `after` in a statement
+fn spaced(before: TokenKind, after: TokenKind) -> bool {
+	!matches!(
+		(before, after),
+		(_, TokenKind::Comma | TokenKind::End | TokenKind::Colon | TokenKind::Question | TokenKind::RSquare)
+		| (TokenKind::LSquare, _)
+		| (TokenKind::LCurly, TokenKind::RCurly)
+	)
+}
+
+/// an expression, with only the parentheses it needs
+/// blocks and conditionals span several lines, indented one level past `indent`
+fn expr(node: &SyntaxNode, indent: usize, comments: &mut Vec<String>) -> String {
+	let nodes = node.nodes().collect::<Vec<&SyntaxNode>>();
+	let tokens = node.tokens().collect::<Vec<&Token>>();
+
+	let text = match node.kind {
+		SyntaxKind::Paren => expr(nodes[0], indent, comments),
+		SyntaxKind::Literal | SyntaxKind::Ref => tokens[0].slice.to_owned(),
+		SyntaxKind::Super => format!("super.{}", tokens[tokens.len() - 1].slice),
+		// an infix operator binds looser than a prefix one, so it always needs parentheses
+		SyntaxKind::Prefix => format!("{}{}", tokens[0].slice, operand(nodes[0], indent, comments, |e| infix_power(e).is_some())),
+		SyntaxKind::Infix => {
+			let op = tokens[0].kind;
+			let (left, right) = op.infix_binding_power().expect("an infix node has an infix operator");
+
+			// `(a + b) * c` and `a - (b - c)` would be parsed the other way around without them
+			let lhs = operand(nodes[0], indent, comments, |e| infix_power(e).is_some_and(|(_, e)| e <= left));
+			let rhs = operand(nodes[1], indent, comments, |e| infix_power(e).is_some_and(|(e, _)| e < right));
+
+			match op {
+				TokenKind::Range | TokenKind::IRange => format!("{}{}{}", lhs, op.symbol(), rhs),
+				_ => format!("{} {} {}", lhs, op.symbol(), rhs),
+			}
+		},
+		SyntaxKind::Call => format!("{}({})", tokens[0].slice, list(&nodes, indent, comments)),
+		SyntaxKind::Array => format!("[{}]", list(&nodes, indent, comments)),
+		SyntaxKind::Index => format!("{}[{}]", postfix_operand(nodes[0], indent, comments), expr(nodes[1], indent, comments)),
+		SyntaxKind::Access => format!("{}.{}", postfix_operand(nodes[0], indent, comments), tokens[tokens.len() - 1].slice),
+		SyntaxKind::Conditional => {
+			let condition = operand(nodes[0], indent, comments, |_| false);
+			let then_block = commented(nodes[1], block(nodes[1], indent), comments);
+			let else_block = match nodes[2].kind {
+				SyntaxKind::Conditional => expr(nodes[2], indent, comments),
+				_ => commented(nodes[2], block(nodes[2], indent), comments),
+			};
+
+			// `else` goes on the line with the closing brace, so it lines up with the `if`
+			format!("if {} {} else {}", condition, then_block, else_block)
+		},
+		SyntaxKind::Block => block(node, indent),
+		kind => unreachable!("a `{:?}` node isn't an expression", kind),
+	};
+
+	commented(node, text, comments)
+}
+
+/// `{`, then each local and the body on lines of their own, then `}` lined up with the line it started on
+fn block(node: &SyntaxNode, indent: usize) -> String {
+	let tabs = "\t".repeat(indent + 1);
+	let mut out = String::from("{\n");
+
+	for e in node.nodes() {
+		let mut comments: Vec<String> = Vec::new();
+
+		let line = match e.kind {
+			SyntaxKind::Local => {
+				let name = e.tokens().next().expect("a local has a name").slice;
+				let value = expr(e.nodes().next().expect("a local has a value"), indent + 1, &mut comments);
+
+				commented(e, format!("{} := {};", name, value), &mut comments)
+			},
+			_ => expr(e, indent + 1, &mut comments),
+		};
+
+		out.push_str(&tabs);
+		out.push_str(&end_line(line, comments));
+		out.push('\n');
+	}
+
+	out.push_str(&"\t".repeat(indent));
+	out.push('}');
+
+	out
+}
+
+fn list(nodes: &[&SyntaxNode], indent: usize, comments: &mut Vec<String>) -> String {
+	nodes.iter().map(|e| expr(e, indent, comments)).collect::<Vec<String>>().join(", ")
+}
+
+/// an expression inside of another, in parentheses if `needs_parentheses` says so
+/// blocks and conditionals always are, so it's clear where they end
+fn operand(node: &SyntaxNode, indent: usize, comments: &mut Vec<String>, needs_parentheses: impl Fn(&SyntaxNode) -> bool) -> String {
+	let inner = unparenthesized(node);
+	let text = expr(inner, indent, comments);
+
+	match matches!(inner.kind, SyntaxKind::Block | SyntaxKind::Conditional) || needs_parentheses(inner) {
+		true => format!("({})", text),
+		false => text,
+	}
+}
+
+/// the left of `lhs[index]` or `lhs.field`, which binds tighter than any operator
+fn postfix_operand(node: &SyntaxNode, indent: usize, comments: &mut Vec<String>) -> String {
+	operand(node, indent, comments, |e| matches!(e.kind, SyntaxKind::Prefix | SyntaxKind::Infix))
+}
+
+fn unparenthesized<'n, 'a>(node: &'n SyntaxNode<'a>) -> &'n SyntaxNode<'a> {
+	match node.kind {
+		SyntaxKind::Paren => unparenthesized(node.nodes().next().expect("parentheses have an expression in them")),
+		_ => node,
+	}
+}
+
+/// the binding power of the operator of an infix node
+fn infix_power(node: &SyntaxNode) -> Option<(u8, u8)> {
+	match node.kind {
+		SyntaxKind::Infix => node.tokens().next()?.kind.infix_binding_power(),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::parser::eval;
+
+	use super::format;
+
+	#[test]
+	fn spacing() {
+		let source = "nice=1+2*3;import port:int   in 1..=5,host as h:[string]?;\nexport nice,port as p\n";
+		let expected = "nice = 1 + 2 * 3;\nimport port: int in 1..=5, host as h: [string]?;\nexport nice, port as p;\n";
+
+		assert_eq!(format(source).unwrap(), expected);
+	}
+
+	#[test]
+	fn parentheses() {
+		let source = "a = ((1 + 2)) * (3 - (4 - 5)) - (6 * 7);\nb = (-x)[0] + -(y + 1) + (z.w)\n";
+		let expected = "a = (1 + 2) * (3 - (4 - 5)) - 6 * 7;\nb = (-x)[0] + -(y + 1) + z.w;\n";
+
+		assert_eq!(format(source).unwrap(), expected);
+	}
+
+	#[test]
+	fn blocks() {
+		let source = "size = if big { s := 10; s * 2 } else if small {1} else { { 5 } }\n";
+		let expected = "size = if big {\n\ts := 10;\n\ts * 2\n} else if small {\n\t1\n} else {\n\t{\n\t\t5\n\t}\n};\n";
+
+		assert_eq!(format(source).unwrap(), expected);
+	}
+
+	#[test]
+	fn comments() {
+		let source = "/// the port\n\n\n   import port = 80 // default\n\n/* kept */ nice = [1 , /* here */ 2];\n// end\n";
+		let expected = "/// the port\n\nimport port = 80; // default\n\n/* kept */\nnice = [1, /* here */ 2];\n// end\n";
+
+		assert_eq!(format(source).unwrap(), expected);
+	}
+
+	#[test]
+	fn inner_comments() {
+		let cases = [
+			"a = { b := 1 /* c */; b }",
+			"a = 1 + // one\n  2",
+			"a = (/* x */ b) * f(c, /* d */ e)",
+			"a = if x { // yes\n 1 } else { 2 /* no */ }",
+			"import /* p */ port: int // the port\n= 80",
+		];
+
+		let formatted = cases.iter().map(|e| format(e).unwrap()).collect::<Vec<String>>();
+
+		assert_eq!(formatted, vec![
+			"a = {\n\tb := 1 /* c */;\n\tb\n};\n",
+			"a = 1 + 2; // one\n",
+			"a = /* x */ b * f(c, /* d */ e);\n",
+			"a = if x {\n\t1 // yes\n} else {\n\t2 /* no */\n};\n",
+			"import /* p */ port: int = 80; // the port\n",
+		]);
+
+		for e in formatted {
+			assert_eq!(format(&e).unwrap(), e);
+		}
+	}
+
+	#[test]
+	fn idempotent() {
+		let source = "import a: { x: int, y: \"b\" | \"c\" } where a.x > 0, b = 2\nfrom \"lib\" import d as e\nc = if (a.x > b) { f(a.x, [b, e]) } else { super.g ** 2 ** -1 }\n";
+		let once = format(source).unwrap();
+
+		assert_eq!(format(&once).unwrap(), once);
+		assert!(once.contains("from \"lib\" import d as e;\n"), "{}", once);
+		assert!(once.contains("if a.x > b {"), "{}", once);
+	}
+
+	#[test]
+	fn same_meaning() {
+		let source = "x = 2; a = (x - 1) - (x - (1 + 1)) ** 2 ** (1 + 0) % 3; b = !(x == 2) || (x > 1 && x < 5);";
+		let store = eval(&format(source).unwrap()).unwrap();
+
+		assert_eq!(store.get("a", None), eval(source).unwrap().get("a", None));
+		assert_eq!(store.get("b", None), eval(source).unwrap().get("b", None));
+	}
+
+	#[test]
+	fn syntax_errors() {
+		assert_eq!(format("nice = ;").unwrap_err().code(), "A0001");
+	}
+}
@@ -1,4 +1,4 @@
-use crate::parser::ast::{Expr, Literal, Store};
+use crate::{parser::{ast::{Expr, Literal, Location, Store, Constraint, ConstraintKind}, errors::{Error, TypeError, UndefinedError, ConstraintError, RuntimeError}}, lexer::tokens::TokenKind};
 
 impl Store {
 	pub fn get<T: Into<String>>(&self, ident: T, history: Option<Vec<String>>) -> Literal {
@@ -10,21 +10,809 @@ impl Store {
 		}
 
 		let found = self.contents.get(&ident);
-		
+
 		history.push(ident);
-		
+
 		match found {
 			Some(e) => e.eval(self, history),
 			None => Literal::None,
 		}
 	}
+
+	/// like `get`, but returns an error for a binding that doesn't exist or can't be evaluated
+	pub fn try_get<T: Into<String>>(&self, ident: T) -> Result<Literal, Error> {
+		let ident = ident.into();
+
+		if !self.contents.contains_key(&ident) {
+			return Err(UndefinedError::new(ident, "global").into());
+		}
+
+		self.get_checked(&ident)
+	}
+
+	/// the value of every export, in the order they were exported
+	/// fails like `try_get` if one can't be evaluated
+	pub fn evaluate_outputs(&self) -> Result<Vec<(String, Literal)>, Error> {
+		self.outputs.iter()
+			.map(|output| Ok((output.name.to_owned(), self.get_checked(&output.binding)?)))
+			.collect()
+	}
+
+	/// the value of a binding that exists, or an error if it refers to itself or an input it depends on isn't valid
+	fn get_checked(&self, ident: &str) -> Result<Literal, Error> {
+		self.check_inputs(ident)?;
+
+		match self.get(ident, None) {
+			Literal::Circular(e) => Err(RuntimeError::new(ident, format!("it refers to itself through `{}`", e)).into()),
+			e => Ok(e),
+		}
+	}
+
+	/// checks the value of every input `ident` depends on, since evaluating one that isn't valid only gives `None`,
+	/// along with the exports of every module it imports
+	fn check_inputs(&self, ident: &str) -> Result<(), Error> {
+		let mut seen: Vec<&str> = Vec::new();
+		let mut stack: Vec<&str> = vec![ident];
+
+		while let Some(name) = stack.pop() {
+			if seen.contains(&name) {
+				continue;
+			}
+
+			seen.push(name);
+
+			let expr = match self.contents.get(name) {
+				Some(e) => e,
+				None => continue,
+			};
+
+			if let Expr::Import(input) = expr.inner() {
+				self.get_input(input.as_str())?;
+
+				if let Some(default) = self.inputs.get(input).and_then(|e| e.default.as_ref()) {
+					stack.extend(default.references());
+				}
+			}
+
+			let mut children = vec![expr];
+
+			while let Some(child) = children.pop() {
+				if let Expr::Module(id) = child {
+					if let Some(module) = self.modules.get(id) {
+						module.evaluate_outputs()?;
+					}
+				}
+
+				children.extend(child.children());
+			}
+
+			stack.extend(expr.references());
+		}
+
+		Ok(())
+	}
+
+	/// sets the value of an input, returning the previous one
+	/// the value is checked against the input's type, then each of its constraints
+	pub fn set_input<T: Into<String>>(&mut self, name: T, value: Literal) -> Result<Option<Literal>, Error> {
+		let name = name.into();
+
+		let input = match self.inputs.get_mut(&name) {
+			Some(e) => e,
+			None => return Err(UndefinedError::new(name, "inputs").into()),
+		};
+
+		let value = match input.static_type.coerce(value.clone()) {
+			Some(e) => e,
+			None => return Err(TypeError::new(name, input.static_type.clone(), value).into()),
+		};
+
+		// constraints refer to the input by name, so they have to see the new value
+		let previous = input.current.replace(value.clone());
+
+		let failed = self.inputs[&name].constraints.iter()
+			.find(|constraint| !self.satisfies(&name, constraint, &value))
+			.map(|constraint| constraint.to_string());
+
+		if let Some(constraint) = failed {
+			if let Some(input) = self.inputs.get_mut(&name) {
+				input.current = previous;
+			}
+
+			return Err(ConstraintError::new(name, constraint, value).into());
+		}
+
+		Ok(previous)
+	}
+
+	/// whether `value` meets a constraint of the input `name`
+	/// the constraint can refer to the input by its own name, and by the name it's bound to
+	fn satisfies(&self, name: &str, constraint: &Constraint, value: &Literal) -> bool {
+		let mut locals: Vec<(String, Literal)> = vec![(name.to_owned(), value.clone())];
+
+		if let Some((binding, _)) = self.contents.iter().find(|(_, e)| matches!(e.inner(), Expr::Import(e) if e == name)) {
+			locals.push((binding.to_owned(), value.clone()));
+		}
+
+		let mut eval = |expr: &Expr| expr.eval_scoped(self, &Vec::new(), &mut locals);
+
+		let result = match (constraint.kind, constraint.expr.inner()) {
+			(ConstraintKind::In, Expr::InfixOp { op: op @ (TokenKind::Range | TokenKind::IRange), lhs, rhs }) => {
+				let upper_op = match op {
+					TokenKind::Range => TokenKind::Lt,
+					_ => TokenKind::Lte,
+				};
+
+				let lower = infix(&TokenKind::Gte, value.clone(), eval(lhs));
+				let upper = infix(&upper_op, value.clone(), eval(rhs));
+
+				infix(&TokenKind::And, lower, upper)
+			},
+			(ConstraintKind::In, expr) => match eval(expr) {
+				Literal::Array(items) => {
+					let value = Expr::Literal(value.clone());
+					Literal::Bool(items.contains(&value))
+				},
+				_ => Literal::None,
+			},
+			(ConstraintKind::Where, expr) => eval(expr),
+		};
+
+		matches!(result, Literal::Bool(true))
+	}
+
+	/// the current value of an input, or its default checked against the input's type and constraints
+	pub fn get_input<T: Into<String>>(&self, name: T) -> Result<Literal, Error> {
+		let name = name.into();
+		let value = self.eval_input(&name, Vec::new())?;
+
+		// a value that was set has been checked already
+		// the constraints of a default are only checked here, since they can refer back to the input through other bindings
+		if self.inputs[&name].current.is_some() || matches!(value, Literal::Circular(_)) {
+			return Ok(value);
+		}
+
+		match self.inputs[&name].constraints.iter().find(|constraint| !self.satisfies(&name, constraint, &value)) {
+			Some(constraint) => {
+				let location = self.default_location(&name);
+
+				Err(ConstraintError::new(name, constraint.to_string(), value).with_location(location).into())
+			},
+			None => Ok(value),
+		}
+	}
+
+	/// where the default of an input was written, or the input itself if it has none
+	fn default_location(&self, name: &str) -> Option<Location> {
+		self.inputs.get(name)
+			.and_then(|e| e.default.as_ref())
+			.and_then(|e| e.location())
+			.or_else(|| self.input_locations.get(name))
+			.cloned()
+	}
+
+	fn eval_input(&self, name: &str, history: Vec<String>) -> Result<Literal, Error> {
+		let input = match self.inputs.get(name) {
+			Some(e) => e,
+			None => return Err(UndefinedError::new(name, "inputs").into()),
+		};
+
+		// an input with neither a value nor a default is `none`, which only optional and untyped inputs accept
+		let default = match (&input.current, &input.default) {
+			(Some(current), _) => return Ok(current.clone()),
+			(None, Some(default)) => default.eval(self, history),
+			(None, None) => Literal::None,
+		};
+
+		match input.static_type.coerce(default.clone()) {
+			Some(e) => Ok(e),
+			// a circular default is reported as-is rather than as the wrong type
+			None if matches!(default, Literal::Circular(_)) => Ok(default),
+			None => Err(TypeError::new(name, input.static_type.clone(), default).with_location(self.default_location(name)).into()),
+		}
+	}
 }
 
 impl Expr {
 	pub(crate) fn eval(&self, store: &Store, history: Vec<String>) -> Literal {
+		self.eval_scoped(store, &history, &mut Vec::new())
+	}
+
+	/// `locals` holds the `:=` bindings of every enclosing block, innermost last
+	fn eval_scoped(&self, store: &Store, history: &Vec<String>, locals: &mut Vec<(String, Literal)>) -> Literal {
 		match self {
+			Expr::Literal(Literal::Array(e)) => {
+				let items = e.iter()
+					.map(|item| Expr::Literal(item.eval_scoped(store, history, locals)))
+					.collect();
+
+				Literal::Array(items)
+			},
 			Expr::Literal(e) => e.clone(),
-			_ => todo!(),
+			Expr::Ref(ident) => {
+				let local = locals.iter().rev().find(|(name, _)| name == ident);
+
+				match local {
+					Some((_, value)) => value.clone(),
+					None => store.get(ident, Some(history.clone())),
+				}
+			},
+			Expr::PrefixOp { op, expr } => {
+				let value = expr.eval_scoped(store, history, locals);
+
+				match (op, value) {
+					(TokenKind::Not, Literal::Bool(e)) => Literal::Bool(!e),
+					(TokenKind::Sub, Literal::Int(e)) => e.checked_neg().map_or(Literal::None, Literal::Int),
+					(TokenKind::Sub, Literal::Float(e)) => Literal::Float(-e),
+					(_, e @ Literal::Circular(_)) => e,
+					_ => Literal::None,
+				}
+			},
+			Expr::InfixOp { op: TokenKind::And, lhs, rhs } => {
+				match lhs.eval_scoped(store, history, locals) {
+					Literal::Bool(true) => rhs.eval_scoped(store, history, locals).into_bool(),
+					Literal::Bool(false) => Literal::Bool(false),
+					e @ Literal::Circular(_) => e,
+					_ => Literal::None,
+				}
+			},
+			Expr::InfixOp { op: TokenKind::Or, lhs, rhs } => {
+				match lhs.eval_scoped(store, history, locals) {
+					Literal::Bool(true) => Literal::Bool(true),
+					Literal::Bool(false) => rhs.eval_scoped(store, history, locals).into_bool(),
+					e @ Literal::Circular(_) => e,
+					_ => Literal::None,
+				}
+			},
+			Expr::InfixOp { op, lhs, rhs } => {
+				let lhs = lhs.eval_scoped(store, history, locals);
+				let rhs = rhs.eval_scoped(store, history, locals);
+
+				infix(op, lhs, rhs)
+			},
+			Expr::Conditional { condition, then_block, else_block } => {
+				match condition.eval_scoped(store, history, locals) {
+					Literal::Bool(true) => then_block.eval_scoped(store, history, locals),
+					Literal::Bool(false) => else_block.eval_scoped(store, history, locals),
+					e @ Literal::Circular(_) => e,
+					_ => Literal::None,
+				}
+			},
+			Expr::ArrayAccess { lhs, index } => {
+				let lhs = lhs.eval_scoped(store, history, locals);
+				let index = index.eval_scoped(store, history, locals);
+
+				match (lhs, index) {
+					(Literal::Array(e), Literal::Int(i)) => {
+						let item = usize::try_from(i).ok().and_then(|i| e.get(i));
+
+						match item {
+							Some(item) => item.eval_scoped(store, history, locals),
+							None => Literal::None,
+						}
+					},
+					(e @ Literal::Circular(_), _) | (_, e @ Literal::Circular(_)) => e,
+					_ => Literal::None,
+				}
+			},
+			Expr::Block { locals: bindings, body } => {
+				let depth = locals.len();
+
+				for (name, value) in bindings {
+					let value = value.eval_scoped(store, history, locals);
+					locals.push((name.to_owned(), value));
+				}
+
+				let out = body.eval_scoped(store, history, locals);
+				locals.truncate(depth);
+
+				out
+			},
+			Expr::Import(name) => store.eval_input(name, history.clone()).unwrap_or(Literal::None),
+			Expr::FnCall { fn_name, args } => {
+				let args = args.iter()
+					.map(|arg| arg.eval_scoped(store, history, locals))
+					.collect();
+
+				call(fn_name, args)
+			},
+			Expr::Module(id) => match store.modules.get(id) {
+				// an export that can't be evaluated is left out, and `check_inputs` reports why
+				Some(module) => {
+					let fields = module.outputs.iter()
+						.filter_map(|output| Some((output.name.to_owned(), Expr::Literal(module.get_checked(&output.binding).ok()?))))
+						.collect();
+
+					Literal::Record(fields)
+				},
+				None => Literal::None,
+			},
+			Expr::Access { lhs, field } => match lhs.eval_scoped(store, history, locals) {
+				Literal::Record(mut fields) => match fields.remove(field) {
+					Some(value) => value.eval_scoped(store, history, locals),
+					None => Literal::None,
+				},
+				e @ Literal::Circular(_) => e,
+				_ => Literal::None,
+			},
+			Expr::Interpolation(parts) => {
+				let mut out = String::new();
+
+				for part in parts {
+					match part.eval_scoped(store, history, locals) {
+						e @ Literal::Circular(_) => return e,
+						e => match e.interpolate() {
+							Some(text) => out += &text,
+							None => return Literal::None,
+						},
+					}
+				}
+
+				Literal::String(out)
+			},
+			Expr::Spanned { expr, .. } => expr.eval_scoped(store, history, locals),
+			// only has a value once the store it's in is layered with `overlay`, which points it at what it refers to
+			Expr::Super(name) => match store.supers.get(name) {
+				Some(e) => e.eval(store, history.clone()),
+				None => Literal::None,
+			},
+			Expr::Scope(_) => Literal::None,
+		}
+	}
+}
+
+impl Literal {
+	fn into_bool(self) -> Literal {
+		match self {
+			e @ Literal::Bool(_) | e @ Literal::Circular(_) => e,
+			_ => Literal::None,
+		}
+	}
+
+	/// the value as text inside of an interpolated string
+	/// floats always have a decimal point, and strings inside of arrays and records are quoted
+	fn interpolate(&self) -> Option<String> {
+		let out = match self {
+			Literal::String(e) => e.to_owned(),
+			Literal::Int(e) => e.to_string(),
+			Literal::Float(e) if e.is_finite() && e.fract() == 0.0 => format!("{:.1}", e),
+			Literal::Float(e) => e.to_string(),
+			Literal::Bool(e) => e.to_string(),
+			Literal::Array(items) => {
+				let items = items.iter()
+					.map(|item| item.inner().nested())
+					.collect::<Option<Vec<String>>>()?;
+
+				format!("[{}]", items.join(", "))
+			},
+			Literal::Record(fields) => {
+				let mut fields = fields.iter()
+					.map(|(name, value)| Some(format!("{}: {}", name, value.inner().nested()?)))
+					.collect::<Option<Vec<String>>>()?;
+				fields.sort();
+
+				format!("{{{}}}", fields.join(", "))
+			},
+			Literal::Circular(_)
+			| Literal::None => return None,
+		};
+
+		Some(out)
+	}
+}
+
+impl Expr {
+	/// an evaluated item of an array or record inside of an interpolated string
+	fn nested(&self) -> Option<String> {
+		match self {
+			Expr::Literal(Literal::String(e)) => Some(format!("{:?}", e)),
+			Expr::Literal(e) => e.interpolate(),
+			_ => None,
 		}
 	}
-}
\ No newline at end of file
+}
+
+fn call(fn_name: &str, args: Vec<Literal>) -> Literal {
+	if let Some(e) = args.iter().find(|arg| matches!(arg, Literal::Circular(_))) {
+		return e.clone();
+	}
+
+	match (fn_name, args.as_slice()) {
+		("len", [Literal::String(e)]) => Literal::Int(e.chars().count() as i64),
+		("len", [Literal::Array(e)]) => Literal::Int(e.len() as i64),
+		("len", [Literal::Record(e)]) => Literal::Int(e.len() as i64),
+		_ => Literal::None,
+	}
+}
+
+/// the most items a range can have, so a file can't use up the memory of whatever evaluates it
+const MAX_RANGE: i128 = 1_000_000;
+
+fn infix(op: &TokenKind, lhs: Literal, rhs: Literal) -> Literal {
+	match (op, lhs, rhs) {
+		(_, e @ Literal::Circular(_), _) | (_, _, e @ Literal::Circular(_)) => e,
+
+		(TokenKind::And, Literal::Bool(lhs), Literal::Bool(rhs)) => Literal::Bool(lhs && rhs),
+		(TokenKind::Or, Literal::Bool(lhs), Literal::Bool(rhs)) => Literal::Bool(lhs || rhs),
+
+		(TokenKind::Equals, lhs, rhs) => Literal::Bool(lhs == rhs),
+		(TokenKind::NotEquals, lhs, rhs) => Literal::Bool(lhs != rhs),
+
+		(TokenKind::Add, Literal::String(lhs), Literal::String(rhs)) => Literal::String(lhs + &rhs),
+		(TokenKind::Add, Literal::Array(mut lhs), Literal::Array(rhs)) => {
+			lhs.extend(rhs);
+			Literal::Array(lhs)
+		},
+
+		(TokenKind::Lt, Literal::String(lhs), Literal::String(rhs)) => Literal::Bool(lhs < rhs),
+		(TokenKind::Gt, Literal::String(lhs), Literal::String(rhs)) => Literal::Bool(lhs > rhs),
+		(TokenKind::Lte, Literal::String(lhs), Literal::String(rhs)) => Literal::Bool(lhs <= rhs),
+		(TokenKind::Gte, Literal::String(lhs), Literal::String(rhs)) => Literal::Bool(lhs >= rhs),
+
+		(TokenKind::Range | TokenKind::IRange, Literal::Int(lhs), Literal::Int(rhs)) => {
+			let (start, end) = (lhs as i128, rhs as i128 + (*op == TokenKind::IRange) as i128);
+
+			match end - start > MAX_RANGE {
+				true => Literal::None,
+				false => Literal::Array((start..end).map(|e| Expr::from(e as i64)).collect()),
+			}
+		},
+
+		(op, Literal::Int(lhs), Literal::Int(rhs)) => {
+			let out = match op {
+				TokenKind::Add => lhs.checked_add(rhs),
+				TokenKind::Sub => lhs.checked_sub(rhs),
+				TokenKind::Mul => lhs.checked_mul(rhs),
+				TokenKind::Div => lhs.checked_div(rhs),
+				TokenKind::Mod => lhs.checked_rem(rhs),
+				TokenKind::Exp => {
+					// negative powers of ints aren't ints
+					return match u32::try_from(rhs) {
+						Ok(rhs) => lhs.checked_pow(rhs).map_or(Literal::None, Literal::Int),
+						Err(_) => Literal::Float((lhs as f64).powf(rhs as f64)),
+					};
+				},
+				TokenKind::Lt => return Literal::Bool(lhs < rhs),
+				TokenKind::Gt => return Literal::Bool(lhs > rhs),
+				TokenKind::Lte => return Literal::Bool(lhs <= rhs),
+				TokenKind::Gte => return Literal::Bool(lhs >= rhs),
+				_ => None,
+			};
+
+			out.map_or(Literal::None, Literal::Int)
+		},
+		(op, Literal::Float(lhs), Literal::Float(rhs)) => float_infix(op, lhs, rhs),
+		(op, Literal::Int(lhs), Literal::Float(rhs)) => float_infix(op, lhs as f64, rhs),
+		(op, Literal::Float(lhs), Literal::Int(rhs)) => float_infix(op, lhs, rhs as f64),
+
+		_ => Literal::None,
+	}
+}
+
+fn float_infix(op: &TokenKind, lhs: f64, rhs: f64) -> Literal {
+	match op {
+		TokenKind::Add => Literal::Float(lhs + rhs),
+		TokenKind::Sub => Literal::Float(lhs - rhs),
+		TokenKind::Mul => Literal::Float(lhs * rhs),
+		TokenKind::Div => Literal::Float(lhs / rhs),
+		TokenKind::Mod => Literal::Float(lhs % rhs),
+		TokenKind::Exp => Literal::Float(lhs.powf(rhs)),
+		TokenKind::Lt => Literal::Bool(lhs < rhs),
+		TokenKind::Gt => Literal::Bool(lhs > rhs),
+		TokenKind::Lte => Literal::Bool(lhs <= rhs),
+		TokenKind::Gte => Literal::Bool(lhs >= rhs),
+		_ => Literal::None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use crate::parser::{eval, ast::{Expr, Literal}};
+
+	#[test]
+	fn block_locals() {
+		let store = eval("total = { base := 10; extra := base * 2; base + extra };").unwrap();
+
+		assert_eq!(store.get("total", None), Literal::Int(30));
+		assert!(matches!(store.get("base", None), Literal::None));
+	}
+
+	#[test]
+	fn block_locals_shadow_globals() {
+		let store = eval(r#"
+			base = 1;
+			total = { base := 10; base * 2 };
+			other = base + 1;
+		"#).unwrap();
+
+		assert_eq!(store.get("total", None), Literal::Int(20));
+		assert_eq!(store.get("other", None), Literal::Int(2));
+	}
+
+	#[test]
+	fn circular() {
+		let store = eval("nice = cool + 1; cool = nice * 2;").unwrap();
+
+		assert!(matches!(store.get("nice", None), Literal::Circular(e) if e == "nice"));
+	}
+
+	#[test]
+	fn negation() {
+		let store = eval("a = 2; b = 3.5; nice = 5-3; cool = 5 - -3; epic = -(a+b); range = -2..1; big = -a ** 2;").unwrap();
+
+		assert_eq!(store.get("nice", None), Literal::Int(2));
+		assert_eq!(store.get("cool", None), Literal::Int(8));
+		assert_eq!(store.get("epic", None), Literal::Float(-5.5));
+		assert_eq!(store.get("range", None), Literal::Array(vec![Expr::from(-2), Expr::from(-1), Expr::from(0)]));
+		assert_eq!(store.get("big", None), Literal::Int(4));
+	}
+
+	#[test]
+	fn ranges() {
+		let store = eval("small = 3..=5; empty = 5..3; huge = 1..1000000000000; widest = -9223372036854775807..=9223372036854775807;").unwrap();
+
+		assert_eq!(store.get("small", None), Literal::Array(vec![Expr::from(3), Expr::from(4), Expr::from(5)]));
+		assert_eq!(store.get("empty", None), Literal::Array(Vec::new()));
+		assert!(matches!(store.get("huge", None), Literal::None));
+		assert!(matches!(store.get("widest", None), Literal::None));
+	}
+
+	#[test]
+	fn interpolation() {
+		let store = eval(r#"
+			host = "localhost";
+			port = 8080;
+			url = "http://{host}:{port}/api";
+			names = ["a"];
+			values = "{1.0} {2.5} {true} {[1, names, [2.0]]}";
+			missing = "{nope}";
+			loop = "{loop}";
+		"#).unwrap();
+
+		assert_eq!(store.get("url", None), Literal::String("http://localhost:8080/api".to_owned()));
+		assert_eq!(store.get("values", None), Literal::String(r#"1.0 2.5 true [1, ["a"], [2.0]]"#.to_owned()));
+		assert!(matches!(store.get("missing", None), Literal::None));
+		assert!(matches!(store.get("loop", None), Literal::Circular(_)));
+	}
+
+	#[test]
+	fn try_get() {
+		let store = eval("nice = cool + 1; cool = nice * 2; epic = 3;").unwrap();
+
+		assert_eq!(store.try_get("epic").unwrap(), Literal::Int(3));
+		assert_eq!(store.try_get("nice").unwrap_err().code(), "A0007");
+		assert_eq!(store.try_get("missing").unwrap_err().code(), "A0003");
+	}
+
+	#[test]
+	fn conditional() {
+		let store = eval(r#"
+			nice = 15;
+			cool = if nice < 10 {
+				10
+			} else if nice < 25 {
+				size := nice * 2;
+				size
+			} else {
+				nice
+			}
+		"#).unwrap();
+
+		assert_eq!(store.get("cool", None), Literal::Int(30));
+	}
+
+	#[test]
+	fn import_default() {
+		let store = eval("import port: int = 8000 + 80; url = port + 1;").unwrap();
+
+		assert_eq!(store.get("url", None), Literal::Int(8081));
+	}
+
+	#[test]
+	fn input_validation() {
+		let mut store = eval("import port: int, ratio: float, name: string = 5;").unwrap();
+
+		assert!(store.set_input("port", Literal::Int(8080)).is_ok());
+		assert_eq!(store.get_input("port").unwrap(), Literal::Int(8080));
+
+		let err = store.set_input("port", Literal::String("80".to_owned())).unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			r#"TypeError: Input `port` expects a value of type `int`, but got `"80"`"#
+		);
+
+		assert!(store.set_input("ratio", Literal::Int(2)).is_ok());
+		assert_eq!(store.get_input("ratio").unwrap(), Literal::Float(2.0));
+
+		let err = store.get_input("name").unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"TypeError: Input `name` expects a value of type `string`, but got `5`"
+		);
+
+		assert!(store.set_input("missing", Literal::Int(1)).is_err());
+	}
+
+	#[test]
+	fn input_default_errors() {
+		let store = eval(r#"import port: int = "x"; a = port + 1; b = [a]; export b;"#).unwrap();
+
+		assert!(matches!(store.get("a", None), Literal::None));
+		assert_eq!(store.try_get("b").unwrap_err().code(), "A0004");
+		assert_eq!(store.evaluate_outputs().unwrap_err().code(), "A0004");
+	}
+
+	#[test]
+	fn missing_inputs() {
+		let mut store = eval("import p: int, maybe: int?, anything; q = p + 1; export q;").unwrap();
+
+		assert_eq!(store.get_input("p").unwrap_err().to_string(), "TypeError: Input `p` expects a value of type `int`, but got `none`");
+		assert!(matches!(store.get_input("maybe").unwrap(), Literal::None));
+		assert!(matches!(store.get_input("anything").unwrap(), Literal::None));
+		assert_eq!(store.try_get("q").unwrap_err().code(), "A0004");
+		assert_eq!(store.evaluate_outputs().unwrap_err().code(), "A0004");
+
+		store.set_input("p", Literal::Int(1)).unwrap();
+		assert_eq!(store.try_get("q").unwrap(), Literal::Int(2));
+	}
+
+	#[test]
+	fn input_validation_deep() {
+		let mut store = eval(r#"import ports: [float], db: { host: string, port: int? }, level: "debug" | "info";"#).unwrap();
+
+		assert!(store.set_input("ports", Literal::Array(vec![Expr::from(1), Expr::from(2.5)])).is_ok());
+		assert_eq!(
+			store.get_input("ports").unwrap(),
+			Literal::Array(vec![Expr::from(1.0), Expr::from(2.5)])
+		);
+		assert!(store.set_input("ports", Literal::Array(vec![Expr::from(true)])).is_err());
+
+		let db = HashMap::from([("host".to_owned(), Expr::from("localhost".to_owned()))]);
+		assert!(store.set_input("db", Literal::Record(db)).is_ok());
+
+		let db = HashMap::from([("host".to_owned(), Expr::from(5))]);
+		let err = store.set_input("db", Literal::Record(db)).unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"TypeError: Input `db` expects a value of type `{ host: string, port: int? }`, but got `{ host: 5 }`"
+		);
+
+		assert!(store.set_input("level", Literal::String("info".to_owned())).is_ok());
+		assert!(store.set_input("level", Literal::String("trace".to_owned())).is_err());
+	}
+
+	#[test]
+	fn input_constraints() {
+		let mut store = eval(r#"
+			import port: int in 1..=65535, name as user: string where len(user) > 0, mode in ["dev", "prod"];
+		"#).unwrap();
+
+		assert!(store.set_input("port", Literal::Int(8080)).is_ok());
+
+		let err = store.set_input("port", Literal::Int(70000)).unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			"ConstraintError: Input `port` must satisfy `in 1..=65535`, but got `70000`"
+		);
+		assert_eq!(store.get_input("port").unwrap(), Literal::Int(8080));
+
+		assert!(store.set_input("name", Literal::String("almond".to_owned())).is_ok());
+
+		let err = store.set_input("name", Literal::String("".to_owned())).unwrap_err();
+		assert_eq!(
+			err.to_string(),
+			r#"ConstraintError: Input `name` must satisfy `where len(user) > 0`, but got `""`"#
+		);
+
+		assert!(store.set_input("mode", Literal::String("dev".to_owned())).is_ok());
+		assert!(store.set_input("mode", Literal::String("test".to_owned())).is_err());
+	}
+
+	#[test]
+	fn input_constraints_defaults() {
+		let mut store = eval(r#"
+			import port: int in 1..10 = 99, name as user: string where len(name) > 0, limited: int where limited < limit = 5;
+			limit = limited + 10;
+			url = "host:{port}";
+		"#).unwrap();
+
+		assert_eq!(
+			store.get_input("port").unwrap_err().to_string(),
+			"ConstraintError: Input `port` must satisfy `in 1..10`, but got `99`"
+		);
+		assert_eq!(store.try_get("url").unwrap_err().code(), "A0005");
+
+		assert!(store.set_input("name", Literal::String("almond".to_owned())).is_ok());
+		assert!(store.set_input("name", Literal::String("".to_owned())).is_err());
+
+		assert_eq!(store.get_input("limited").unwrap(), Literal::Int(5));
+		assert_eq!(store.try_get("limit").unwrap(), Literal::Int(15));
+	}
+
+	#[test]
+	fn outputs() {
+		let mut store = eval(r#"
+			import port: int = 80;
+			host = "localhost";
+			export host, url as address;
+			url = "http://" + host;
+			export doubled = port * 2;
+		"#).unwrap();
+
+		store.set_input("port", Literal::Int(8080)).unwrap();
+
+		assert_eq!(
+			store.evaluate_outputs().unwrap(),
+			vec![
+				("host".to_owned(), Literal::String("localhost".to_owned())),
+				("address".to_owned(), Literal::String("http://localhost".to_owned())),
+				("doubled".to_owned(), Literal::Int(16160)),
+			]
+		);
+	}
+
+	#[test]
+	fn outputs_circular() {
+		let store = eval("a = b; b = a; c = 1; export c, a;").unwrap();
+
+		let err = store.evaluate_outputs().unwrap_err();
+		assert_eq!(err.code(), "A0007");
+		assert_eq!(err.to_string(), store.try_get("a").unwrap_err().to_string());
+	}
+
+	#[test]
+	fn overlay() {
+		let base = eval(r#"
+			import port: int = 80;
+			replicas = 3;
+			total = replicas * 10;
+			export total;
+		"#).unwrap();
+
+		let staging = eval(r#"
+			replicas = super.replicas * 2;
+			import port = super.port + 8000;
+		"#).unwrap();
+
+		let prod = eval(r#"
+			replicas = super.replicas + 1;
+			name = "prod";
+			export name;
+		"#).unwrap();
+
+		let store = base.overlay(staging).unwrap().overlay(prod).unwrap();
+
+		assert_eq!(store.get("replicas", None), Literal::Int(7));
+		assert!(store.contents.keys().all(|e| !e.contains('@')));
+		assert_eq!(store.get_input("port").unwrap(), Literal::Int(8080));
+		assert_eq!(
+			store.evaluate_outputs().unwrap(),
+			vec![
+				("total".to_owned(), Literal::Int(70)),
+				("name".to_owned(), Literal::String("prod".to_owned())),
+			]
+		);
+	}
+
+	#[test]
+	fn overlay_layered() {
+		let lower = eval("r = 1").unwrap().overlay(eval("r = super.r + 10\nkeep = super.r").unwrap()).unwrap();
+		let upper = eval("r = 100").unwrap().overlay(eval("other = super.r").unwrap()).unwrap();
+
+		let store = lower.overlay(upper).unwrap();
+
+		assert_eq!(store.get("keep", None), Literal::Int(1));
+		assert_eq!(store.get("other", None), Literal::Int(100));
+		assert_eq!(store.get("r", None), Literal::Int(100));
+	}
+
+	#[test]
+	fn overlay_undefined_super() {
+		let base = eval("replicas = 3;").unwrap();
+		let layer = eval("cpus = super.cpus * 2;").unwrap();
+
+		assert!(base.overlay(layer).is_err());
+	}
+}
@@ -1 +1,71 @@
-pub mod tokens;
\ No newline at end of file
+use std::ops::Range;
+
+use logos::Logos;
+
+use self::tokens::TokenKind;
+
+pub mod tokens;
+
+/// a token in a source file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token<'a> {
+	pub kind: TokenKind,
+	/// the byte offsets of the token in the source
+	pub span: Range<usize>,
+	pub slice: &'a str,
+}
+
+/// splits `source` into the tokens the parser sees, leaving out whitespace and comments
+pub fn tokenize(source: &str) -> Vec<Token<'_>> {
+	tokenize_with_trivia(source).into_iter()
+		.filter(|e| !e.kind.is_trivia())
+		.collect()
+}
+
+/// splits `source` into every token in it, including whitespace and comments,
+/// so the slices of the tokens put together are the whole source
+pub fn tokenize_with_trivia(source: &str) -> Vec<Token<'_>> {
+	let mut lexer = TokenKind::lexer(source);
+	let mut out: Vec<Token> = Vec::new();
+
+	while let Some(kind) = lexer.next() {
+		out.push(Token { kind, span: lexer.span(), slice: lexer.slice() });
+	}
+
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{tokenize, tokenize_with_trivia, Token, tokens::TokenKind};
+
+	#[test]
+	fn tokens() {
+		let tokens = tokenize("nice = 1 + 2; // sum");
+
+		assert_eq!(tokens[0], Token { kind: TokenKind::Ident, span: 0..4, slice: "nice" });
+		assert_eq!(
+			tokens.iter().map(|e| e.kind).collect::<Vec<TokenKind>>(),
+			vec![TokenKind::Ident, TokenKind::Assign, TokenKind::Int(1), TokenKind::Add, TokenKind::Int(2), TokenKind::End]
+		);
+	}
+
+	#[test]
+	fn trivia() {
+		let source = "/// docs\nimport port /* inline */ = 80;\n";
+		let tokens = tokenize_with_trivia(source);
+
+		assert_eq!(tokens.iter().map(|e| e.slice).collect::<String>(), source);
+		assert_eq!(tokens[0].kind, TokenKind::DocComment);
+		assert!(tokens.iter().any(|e| e.kind == TokenKind::BlockComment && e.slice == "/* inline */"));
+	}
+
+	#[test]
+	fn display() {
+		assert_eq!(TokenKind::Add.to_string(), "+");
+		assert_eq!(TokenKind::Equals.to_string(), "==");
+		assert_eq!(TokenKind::Import.to_string(), "import");
+		assert_eq!(TokenKind::Export.to_string(), "export");
+		assert_eq!(TokenKind::Int(5).to_string(), "int");
+	}
+}
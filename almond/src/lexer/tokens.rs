@@ -1,33 +1,53 @@
 use logos::Logos;
 use std::fmt;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Logos, Clone, Copy, Debug, PartialEq)]
 pub enum TokenKind {
     // ===== general =====
-    #[regex(r"[a-zA-Z][\w_]*")]
+    #[regex(r"[\p{XID_Start}_]\p{XID_Continue}*")]
+	#[regex(r"`[^`\n]+`")]
     Ident,
     #[token("=")]
     Assign,
 	#[token(":=")]
 	Walrus,
+	#[token(":")]
+	Colon,
     #[token(";")]
     End,
     #[regex(r"\$[a-zA-Z][\w_]*")]
     Scope,
     #[token("import")]
     Import,
+	#[token("from")]
+	From,
+	#[token("super")]
+	Super,
 	#[token("export")]
 	Export,
     #[token("as")]
     As,
+	#[token("in")]
+	In,
+	#[token("where")]
+	Where,
     #[token(",")]
     Comma,
+	#[token("?")]
+	Question,
+	#[token("|")]
+	Pipe,
     #[token(".")]
     Access,
-    #[regex(r"[ \t\n\f]+", logos::skip)]
+    #[regex(r"[ \t\r\n\f]+")]
     Whitespace,
     #[regex(r"//[^\n]*")]
     Comment,
+	#[token("/*", block_comment)]
+	BlockComment,
+	#[regex(r"///[^\n]*")]
+	DocComment,
     #[error]
     Error,
 	EOF,
@@ -89,12 +109,19 @@ pub enum TokenKind {
     Exp,
 
     // ===== literal =====
-    #[regex(r#""[^"]*""#)]
-	#[regex(r#"'[^']*'"#)]
+    #[regex(r#""([^"\\]|\\.)*""#)]
+	#[regex(r#"'([^'\\]|\\.)*'"#)]
+	#[regex(r#""""([^"\\]|\\.|"[^"]|""[^"])*""""#)]
+	#[regex(r#"r"[^"]*""#)]
+	#[regex(r##"r#"([^"]|"[^#])*"#"##)]
     String,
-    #[regex(r"-?\d[\d_]*", |lex| lex.slice().parse())]
+    #[regex(r"\d[\d_]*", |lex| parse_int(lex.slice()).ok())]
+	#[regex(r"0[xX][\da-fA-F_]*", |lex| parse_int(lex.slice()).ok())]
+	#[regex(r"0[oO][0-7_]*", |lex| parse_int(lex.slice()).ok())]
+	#[regex(r"0[bB][01_]*", |lex| parse_int(lex.slice()).ok())]
     Int(i64),
-    #[regex(r"-?(\d[\d_]*)?\.(\d[\d_]*)([eE]?\d[\d_]*)?", |lex| lex.slice().parse())]
+    #[regex(r"(\d[\d_]*)?\.\d[\d_]*([eE][+-]?[\d_]*)?", |lex| parse_float(lex.slice()).ok())]
+	#[regex(r"\d[\d_]*[eE][+-]?[\d_]*", |lex| parse_float(lex.slice()).ok())]
     Float(f64),
     #[token("true")]
     True,
@@ -102,67 +129,237 @@ pub enum TokenKind {
     False,
 }
 
-impl fmt::Display for TokenKind {
-	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-		write!(f, "{}", String::from(self))
+/// finds the end of a `/* */` comment, which can have other block comments inside of it
+fn block_comment(lex: &mut logos::Lexer<TokenKind>) -> bool {
+	let mut depth = 1;
+	let mut rest = lex.remainder().char_indices().peekable();
+
+	while let Some((i, e)) = rest.next() {
+		match (e, rest.peek().map(|(_, e)| *e)) {
+			('/', Some('*')) => depth += 1,
+			('*', Some('/')) => depth -= 1,
+			_ => continue,
+		}
+
+		rest.next();
+
+		if depth == 0 {
+			lex.bump(i + 2);
+			return true;
+		}
+	}
+
+	false
+}
+
+impl TokenKind {
+	/// whitespace and comments, which the parser skips over wherever they are
+	pub fn is_trivia(&self) -> bool {
+		matches!(self, TokenKind::Whitespace | TokenKind::Comment | TokenKind::BlockComment | TokenKind::DocComment)
+	}
+}
+
+/// parses an int literal like `1_000`, `0xff`, `0o17` or `0b1010`
+/// fails with why it isn't an int, which is that it has no digits after its prefix or that it doesn't fit in an `i64`
+fn parse_int(slice: &str) -> Result<i64, String> {
+	let (radix, digits) = int_digits(slice);
+
+	if digits.is_empty() {
+		return Err(format!("the int literal has no digits after `{}`", &slice[..2]));
+	}
+
+	i64::from_str_radix(&digits, radix).map_err(|_| format!("the int literal is too large, the largest int is `{}`", i64::MAX))
+}
+
+/// whether `slice` is the int literal one past the largest int, which is the smallest int once it's negated
+pub(crate) fn is_min_int_magnitude(slice: &str) -> bool {
+	let (radix, digits) = int_digits(slice);
+
+	u64::from_str_radix(&digits, radix) == Ok(i64::MIN.unsigned_abs())
+}
+
+/// the radix of an int literal, and its digits without the prefix or underscores
+fn int_digits(slice: &str) -> (u32, String) {
+	let digits = slice.replace('_', "");
+
+	match digits.get(..2) {
+		Some("0x" | "0X") => (16, digits[2..].to_owned()),
+		Some("0o" | "0O") => (8, digits[2..].to_owned()),
+		Some("0b" | "0B") => (2, digits[2..].to_owned()),
+		_ => (10, digits),
+	}
+}
+
+/// parses a float literal like `2.5`, `1_000.5` or `2.5e-3`
+/// fails with why it isn't a float, which is that it has no digits after its exponent or that it's too large to be finite
+fn parse_float(slice: &str) -> Result<f64, String> {
+	if let Some(i) = slice.find(['e', 'E']) {
+		if slice[i + 1..].trim_start_matches(['+', '-']).replace('_', "").is_empty() {
+			return Err(format!("the float literal has no digits after `{}`", slice[i..].trim_end_matches('_')));
+		}
+	}
+
+	match slice.replace('_', "").parse::<f64>() {
+		Ok(e) if e.is_finite() => Ok(e),
+		_ => Err("the float literal is too large".to_owned()),
+	}
+}
+
+/// why the source that lexed as `TokenKind::Error` isn't a valid token
+/// numbers say why themselves
+pub(crate) fn describe_error(slice: &str) -> String {
+	let is_hex = matches!(slice.get(..2), Some("0x" | "0X"));
+
+	let error = match !is_hex && slice.contains(['.', 'e', 'E']) {
+		true => parse_float(slice).err(),
+		false => parse_int(slice).err(),
+	};
+
+	match slice.chars().next() {
+		Some(e) if e.is_ascii_digit() || e == '.' => error.unwrap_or_else(|| format!("`{}` isn't valid here", slice)),
+		Some('"' | '\'') => "the string is never closed".to_owned(),
+		Some('/') if slice.starts_with("/*") => "the block comment is never closed".to_owned(),
+		Some(e) => format!("`{}` isn't valid here", e),
+		None => "unexpected end of file".to_owned(),
 	}
 }
 
-impl From<&TokenKind> for String {
-	fn from(other: &TokenKind) -> String {
-		let out = match other {
-			TokenKind::Ident => "Ident",
-			TokenKind::Assign => "Assign",
-			TokenKind::Walrus => "Walrus",
-			TokenKind::End => "End",
-			TokenKind::Scope => "Scope",
-			TokenKind::Import => "Export",
-			TokenKind::Export => "Import",
-			TokenKind::As => "As",
-			TokenKind::Comma => "Comma",
-			TokenKind::Access => "Access",
-			TokenKind::Whitespace => "",
-			TokenKind::Comment => "Comment",
-			TokenKind::Error => "Error",
-			TokenKind::EOF => "EOF",
-			TokenKind::Range => "Range",
-			TokenKind::IRange => "IRange",
-			TokenKind::LCurly => "LCurly",
-			TokenKind::RCurly => "RCurly",
-			TokenKind::LSquare => "LSquare",
-			TokenKind::RSquare => "RSquare",
-			TokenKind::LParen => "LParen",
-			TokenKind::RParen => "RParen",
-			TokenKind::Equals => "Equals",
-			TokenKind::NotEquals => "NotEquals",
-			TokenKind::Lt => "Lt",
-			TokenKind::Gt => "Gt",
-			TokenKind::Lte => "Lte",
-			TokenKind::Gte => "Gte",
-			TokenKind::And => "And",
-			TokenKind::Or => "Or",
-			TokenKind::Not => "Not",
-			TokenKind::If => "If",
-			TokenKind::Else => "Else",
-			TokenKind::Add => "Add",
-			TokenKind::Sub => "Sub",
-			TokenKind::Mul => "Mul",
-			TokenKind::Div => "Div",
-			TokenKind::Mod => "Mod",
-			TokenKind::Exp => "Exp",
-			TokenKind::String => "String",
-			TokenKind::Int(_) => "Int",
-			TokenKind::Float(_) => "Float",
-			TokenKind::True => "True",
-			TokenKind::False => "False",
-		};
-
-		out.to_owned()
+impl TokenKind {
+	/// the token as it's written, or a description of it for tokens like numbers that can be written many ways
+	pub fn symbol(&self) -> &'static str {
+		match self {
+			TokenKind::Ident => "identifier",
+			TokenKind::Assign => "=",
+			TokenKind::Walrus => ":=",
+			TokenKind::Colon => ":",
+			TokenKind::End => ";",
+			TokenKind::Scope => "scope",
+			TokenKind::Import => "import",
+			TokenKind::Export => "export",
+			TokenKind::From => "from",
+			TokenKind::Super => "super",
+			TokenKind::As => "as",
+			TokenKind::In => "in",
+			TokenKind::Where => "where",
+			TokenKind::Comma => ",",
+			TokenKind::Question => "?",
+			TokenKind::Pipe => "|",
+			TokenKind::Access => ".",
+			TokenKind::Whitespace => "whitespace",
+			TokenKind::Comment => "comment",
+			TokenKind::BlockComment => "block comment",
+			TokenKind::DocComment => "doc comment",
+			TokenKind::Error => "invalid token",
+			TokenKind::EOF => "end of file",
+			TokenKind::Range => "..",
+			TokenKind::IRange => "..=",
+			TokenKind::LCurly => "{",
+			TokenKind::RCurly => "}",
+			TokenKind::LSquare => "[",
+			TokenKind::RSquare => "]",
+			TokenKind::LParen => "(",
+			TokenKind::RParen => ")",
+			TokenKind::Equals => "==",
+			TokenKind::NotEquals => "!=",
+			TokenKind::Lt => "<",
+			TokenKind::Gt => ">",
+			TokenKind::Lte => "<=",
+			TokenKind::Gte => ">=",
+			TokenKind::And => "&&",
+			TokenKind::Or => "||",
+			TokenKind::Not => "!",
+			TokenKind::If => "if",
+			TokenKind::Else => "else",
+			TokenKind::Add => "+",
+			TokenKind::Sub => "-",
+			TokenKind::Mul => "*",
+			TokenKind::Div => "/",
+			TokenKind::Mod => "%",
+			TokenKind::Exp => "**",
+			TokenKind::String => "string",
+			TokenKind::Int(_) => "int",
+			TokenKind::Float(_) => "float",
+			TokenKind::True => "true",
+			TokenKind::False => "false",
+		}
+	}
+}
+
+impl fmt::Display for TokenKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.symbol())
 	}
 }
 
 impl AsRef<TokenKind> for TokenKind {
 	fn as_ref(&self) -> &TokenKind {
-		&self
+		self
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use logos::Logos;
+
+	use crate::parser::{eval, ast::Literal};
+
+	use super::TokenKind;
+
+	fn lex(source: &str) -> Vec<TokenKind> {
+		TokenKind::lexer(source).filter(|e| !e.is_trivia()).collect()
+	}
+
+	#[test]
+	fn numbers() {
+		assert_eq!(lex("1_000_000"), vec![TokenKind::Int(1_000_000)]);
+		assert_eq!(lex("0xff 0XdEaD_bEeF"), vec![TokenKind::Int(0xff), TokenKind::Int(0xdead_beef)]);
+		assert_eq!(lex("0o17 0b1010_1010"), vec![TokenKind::Int(0o17), TokenKind::Int(0b1010_1010)]);
+		assert_eq!(lex("1e10 2.5e-3 1_000.5 .5"), vec![
+			TokenKind::Float(1e10),
+			TokenKind::Float(2.5e-3),
+			TokenKind::Float(1000.5),
+			TokenKind::Float(0.5),
+		]);
+		assert_eq!(lex("1..5"), vec![TokenKind::Int(1), TokenKind::Range, TokenKind::Int(5)]);
+	}
+
+	#[test]
+	fn overflow() {
+		let err = eval("nice = 9223372036854775808;").unwrap_err();
+
+		assert_eq!(err.code(), "A0009");
+		assert_eq!(
+			err.to_string(),
+			"LexError: Invalid token `9223372036854775808` at char `7`: the int literal is too large, the largest int is `9223372036854775807`"
+		);
+
+		let err = eval("nice = 1e999;").unwrap_err();
+		assert!(err.to_string().ends_with("the float literal is too large"));
+
+		assert!(eval("nice = 9223372036854775807;").is_ok());
+
+		let store = eval("min = -9_223_372_036_854_775_808; hex = -0x8000000000000000;").unwrap();
+		assert_eq!(store.get("min", None), Literal::Int(i64::MIN));
+		assert_eq!(store.get("hex", None), Literal::Int(i64::MIN));
+		assert!(eval("nice = 1 - 9223372036854775808;").is_err());
+
+		let err = eval("nice = 0x_;").unwrap_err();
+		assert!(err.to_string().ends_with("the int literal has no digits after `0x`"), "{}", err);
+
+		let err = eval("nice = 0x;").unwrap_err();
+		assert_eq!(err.to_string(), "LexError: Invalid token `0x` at char `7`: the int literal has no digits after `0x`");
+
+		let err = eval("nice = 0b;").unwrap_err();
+		assert!(err.to_string().ends_with("the int literal has no digits after `0b`"), "{}", err);
+
+		let err = eval("nice = 1.5e;").unwrap_err();
+		assert_eq!(err.to_string(), "LexError: Invalid token `1.5e` at char `7`: the float literal has no digits after `e`");
+
+		let err = eval("nice = 2E+_;").unwrap_err();
+		assert!(err.to_string().ends_with("the float literal has no digits after `E+`"), "{}", err);
+
+		let store = eval("nice = 1_000.5e-1_0; cool = 2e3;").unwrap();
+		assert_eq!(store.get("nice", None), Literal::Float(1000.5e-10));
+		assert_eq!(store.get("cool", None), Literal::Float(2000.0));
 	}
 }
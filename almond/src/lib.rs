@@ -1,3 +1,4 @@
-pub(crate) mod lexer;
+pub mod lexer;
 pub mod parser;
-mod interpreter;
\ No newline at end of file
+pub mod formatter;
+mod interpreter;
@@ -0,0 +1,67 @@
+use std::{env, fs, process};
+
+use almond::{formatter::format, parser::{diagnostics::SourceMap, errors::{AlmondError, IoError}}};
+
+const USAGE: &str = "usage: almond fmt [--check] <files>...";
+
+fn main() {
+	let args: Vec<String> = env::args().skip(1).collect();
+
+	let code = match args.first().map(String::as_str) {
+		Some("fmt") => fmt(&args[1..]),
+		_ => {
+			eprintln!("{}", USAGE);
+			2
+		},
+	};
+
+	process::exit(code);
+}
+
+/// formats every file named in `args` in place
+/// with `--check`, lists the files that aren't formatted instead, exiting with 1 if there are any
+fn fmt(args: &[String]) -> i32 {
+	let check = args.iter().any(|e| e == "--check");
+	let paths: Vec<&String> = args.iter().filter(|e| *e != "--check").collect();
+
+	if paths.is_empty() {
+		eprintln!("{}", USAGE);
+		return 2;
+	}
+
+	let mut code = 0;
+
+	for path in paths {
+		let source = match fs::read_to_string(path) {
+			Ok(e) => e,
+			Err(e) => {
+				eprintln!("{}", IoError::new(path.as_str(), e.to_string()));
+				code = 1;
+				continue;
+			},
+		};
+
+		let formatted = match format(&source) {
+			Ok(e) => e,
+			Err(e) => {
+				eprint!("{}", e.diagnostic().render(&SourceMap::named(path, &source), false));
+				code = 1;
+				continue;
+			},
+		};
+
+		if formatted == source {
+			continue;
+		}
+
+		if check {
+			println!("{} isn't formatted", path);
+			code = 1;
+		} else if let Err(e) = fs::write(path, formatted) {
+			eprintln!("{}", IoError::new(path.as_str(), e.to_string()));
+			code = 1;
+		}
+	}
+
+	code
+}
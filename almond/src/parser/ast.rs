@@ -1,19 +1,131 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, ops::Range, sync::atomic::{AtomicUsize, Ordering}};
 
 use crate::lexer::tokens::TokenKind;
 
-use super::errors::NameConflictError;
+use super::errors::{NameConflictError, UndefinedError};
 
-#[derive(Debug)]
+/// how many layers have been put on top of a store with `overlay`, so the names `super` is kept under are never reused,
+/// even when layering stores that were already layered
+static LAYERS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum InputType {
 	String,
 	Int,
 	Float,
 	Bool,
-	Array,
+	/// `[int]`, or `array` for an array of anything
+	Array(Box<InputType>),
+	/// `int?`, also accepts no value
+	Optional(Box<InputType>),
+	/// `{ host: string, port: int }`
+	Record { fields: Vec<(String, InputType)> },
+	/// `"debug" | "info" | "warn"`
+	Enum(Vec<String>),
 	Any,
 }
 
+impl InputType {
+	/// the type named by an annotation like `port: int`
+	pub fn from_name(name: &str) -> Option<InputType> {
+		let out = match name {
+			"string" => InputType::String,
+			"int" => InputType::Int,
+			"float" => InputType::Float,
+			"bool" => InputType::Bool,
+			"array" => InputType::Array(Box::new(InputType::Any)),
+			"any" => InputType::Any,
+			_ => return None,
+		};
+
+		Some(out)
+	}
+
+	/// returns the value if it fits this type, with ints widened to floats where a float is expected
+	pub fn coerce(&self, value: Literal) -> Option<Literal> {
+		match (self, value) {
+			(InputType::Any, e) => Some(e),
+			(InputType::String, e @ Literal::String(_))
+			| (InputType::Int, e @ Literal::Int(_))
+			| (InputType::Float, e @ Literal::Float(_))
+			| (InputType::Bool, e @ Literal::Bool(_)) => Some(e),
+			(InputType::Float, Literal::Int(e)) => Some(Literal::Float(e as f64)),
+			(InputType::Optional(_), Literal::None) => Some(Literal::None),
+			(InputType::Optional(inner), e) => inner.coerce(e),
+			(InputType::Enum(variants), Literal::String(e)) if variants.contains(&e) => Some(Literal::String(e)),
+			(InputType::Array(inner), Literal::Array(items)) => {
+				let items = items.into_iter()
+					.map(|item| match item {
+						Expr::Literal(e) => inner.coerce(e).map(Expr::Literal),
+						_ => None,
+					})
+					.collect::<Option<Vec<Expr>>>()?;
+
+				Some(Literal::Array(items))
+			},
+			(InputType::Record { fields }, Literal::Record(mut values)) => {
+				let mut out = HashMap::new();
+
+				for (name, field_type) in fields {
+					let value = match values.remove(name) {
+						Some(Expr::Literal(e)) => e,
+						Some(_) => return None,
+						None => Literal::None,
+					};
+
+					out.insert(name.to_owned(), Expr::Literal(field_type.coerce(value)?));
+				}
+
+				// fields the type doesn't mention are most likely typos
+				if !values.is_empty() {
+					return None;
+				}
+
+				Some(Literal::Record(out))
+			},
+			_ => None,
+		}
+	}
+}
+
+impl fmt::Display for InputType {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			InputType::String => write!(f, "string"),
+			InputType::Int => write!(f, "int"),
+			InputType::Float => write!(f, "float"),
+			InputType::Bool => write!(f, "bool"),
+			InputType::Array(e) => write!(f, "[{}]", e),
+			InputType::Optional(e) => write!(f, "{}?", e),
+			InputType::Record { fields } => {
+				write!(f, "{{ ")?;
+
+				for (i, (name, field_type)) in fields.iter().enumerate() {
+					if i > 0 {
+						write!(f, ", ")?;
+					}
+
+					write!(f, "{}: {}", name, field_type)?;
+				}
+
+				write!(f, " }}")
+			},
+			InputType::Enum(variants) => {
+				for (i, variant) in variants.iter().enumerate() {
+					if i > 0 {
+						write!(f, " | ")?;
+					}
+
+					write!(f, "{:?}", variant)?;
+				}
+
+				Ok(())
+			},
+			InputType::Any => write!(f, "any"),
+		}
+	}
+}
+
 #[derive(Debug, Clone)]
 pub enum Literal {
     String(String),
@@ -21,6 +133,7 @@ pub enum Literal {
     Float(f64),
     Bool(bool),
 	Array(Vec<Expr>),
+	Record(HashMap<String, Expr>),
 	Circular(String),
 	None,
 }
@@ -35,6 +148,7 @@ impl PartialEq<Literal> for Literal {
 			(Literal::Int(e), Literal::Float(s)) => *e as f64 == *s,
 			(Literal::Bool(e), Literal::Bool(s)) => e == s,
 			(Literal::Array(e), Literal::Array(s)) => e == s,
+			(Literal::Record(e), Literal::Record(s)) => e == s,
 			(Literal::Circular(_), Literal::Circular(_)) => false,
 			(Literal::None, Literal::None) => false,
 			_ => false,
@@ -42,7 +156,63 @@ impl PartialEq<Literal> for Literal {
 	}
 }
 
+impl fmt::Display for Literal {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Literal::String(e) => write!(f, "{:?}", e),
+			Literal::Int(e) => write!(f, "{}", e),
+			Literal::Float(e) => write!(f, "{:?}", e),
+			Literal::Bool(e) => write!(f, "{}", e),
+			Literal::Array(e) => {
+				write!(f, "[")?;
+
+				for (i, item) in e.iter().enumerate() {
+					if i > 0 {
+						write!(f, ", ")?;
+					}
+
+					match item.inner() {
+						Expr::Literal(item) => write!(f, "{}", item)?,
+						_ => write!(f, "..")?,
+					}
+				}
+
+				write!(f, "]")
+			},
+			Literal::Record(e) => {
+				let mut fields: Vec<_> = e.iter().collect();
+				fields.sort_by(|a, b| a.0.cmp(b.0));
+
+				write!(f, "{{ ")?;
+
+				for (i, (name, value)) in fields.into_iter().enumerate() {
+					if i > 0 {
+						write!(f, ", ")?;
+					}
+
+					match value {
+						Expr::Literal(value) => write!(f, "{}: {}", name, value)?,
+						_ => write!(f, "{}: ..", name)?,
+					}
+				}
+
+				write!(f, " }}")
+			},
+			Literal::Circular(e) => write!(f, "<circular reference to `{}`>", e),
+			Literal::None => write!(f, "none"),
+		}
+	}
+}
+
+/// where something was written
 #[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+	/// the id of the file it's in, if it came from a module resolver
+	pub file: Option<String>,
+	pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
 pub enum Expr {
 	Literal(Literal),
 	Ref(String),
@@ -52,7 +222,148 @@ pub enum Expr {
 	InfixOp { op: TokenKind, lhs: Box<Expr>, rhs: Box<Expr> },
     Conditional { condition: Box<Expr>, then_block: Box<Expr>, else_block: Box<Expr> },
 	ArrayAccess { lhs: Box<Expr>, index: Box<Expr> },
-	Import,
+	/// `{ name := value; ... body }`, the locals are only visible inside the block
+	Block { locals: Vec<(String, Expr)>, body: Box<Expr> },
+	/// the value of the input with this name, set by the host
+	Import(String),
+	/// the exports of an imported file as a record, by the id its resolver gave it
+	Module(String),
+	/// `lhs.field`, for records and modules
+	Access { lhs: Box<Expr>, field: String },
+	/// `super.name`, the value of `name` in the layer below this one
+	Super(String),
+	/// `"https://{host}:{port}"`, the string made by joining each part together
+	Interpolation(Vec<Expr>),
+	/// an expression along with where it was written, which is ignored when comparing expressions
+	Spanned { location: Location, expr: Box<Expr> },
+}
+
+impl PartialEq for Expr {
+	fn eq(&self, other: &Expr) -> bool {
+		match (self.inner(), other.inner()) {
+			(Expr::Literal(e), Expr::Literal(s)) => e == s,
+			(Expr::Ref(e), Expr::Ref(s)) => e == s,
+			(Expr::Scope(e), Expr::Scope(s)) => e == s,
+			(Expr::FnCall { fn_name, args }, Expr::FnCall { fn_name: s_name, args: s_args }) => fn_name == s_name && args == s_args,
+			(Expr::PrefixOp { op, expr }, Expr::PrefixOp { op: s_op, expr: s_expr }) => op == s_op && expr == s_expr,
+			(Expr::InfixOp { op, lhs, rhs }, Expr::InfixOp { op: s_op, lhs: s_lhs, rhs: s_rhs }) => {
+				op == s_op && lhs == s_lhs && rhs == s_rhs
+			},
+			(
+				Expr::Conditional { condition, then_block, else_block },
+				Expr::Conditional { condition: s_condition, then_block: s_then, else_block: s_else },
+			) => condition == s_condition && then_block == s_then && else_block == s_else,
+			(Expr::ArrayAccess { lhs, index }, Expr::ArrayAccess { lhs: s_lhs, index: s_index }) => lhs == s_lhs && index == s_index,
+			(Expr::Block { locals, body }, Expr::Block { locals: s_locals, body: s_body }) => locals == s_locals && body == s_body,
+			(Expr::Import(e), Expr::Import(s)) => e == s,
+			(Expr::Module(e), Expr::Module(s)) => e == s,
+			(Expr::Access { lhs, field }, Expr::Access { lhs: s_lhs, field: s_field }) => lhs == s_lhs && field == s_field,
+			(Expr::Super(e), Expr::Super(s)) => e == s,
+			(Expr::Interpolation(e), Expr::Interpolation(s)) => e == s,
+			_ => false,
+		}
+	}
+}
+
+impl Expr {
+	/// the expression without the spans around it
+	pub fn inner(&self) -> &Expr {
+		match self {
+			Expr::Spanned { expr, .. } => expr.inner(),
+			e => e,
+		}
+	}
+
+	/// where the expression was written, if it came from a parser
+	pub fn location(&self) -> Option<&Location> {
+		match self {
+			Expr::Spanned { location, .. } => Some(location),
+			_ => None,
+		}
+	}
+
+	/// every expression directly inside of this one
+	pub fn children(&self) -> Vec<&Expr> {
+		match self {
+			Expr::Literal(Literal::Array(e)) => e.iter().collect(),
+			Expr::Literal(Literal::Record(e))
+			| Expr::Scope(e) => e.values().collect(),
+			Expr::FnCall { args, .. }
+			| Expr::Interpolation(args) => args.iter().collect(),
+			Expr::PrefixOp { expr, .. } => vec![expr],
+			Expr::InfixOp { lhs, rhs, .. } => vec![lhs, rhs],
+			Expr::Conditional { condition, then_block, else_block } => vec![condition, then_block, else_block],
+			Expr::ArrayAccess { lhs, index } => vec![lhs, index],
+			Expr::Block { locals, body } => {
+				let mut out: Vec<&Expr> = locals.iter().map(|(_, e)| e).collect();
+				out.push(body);
+
+				out
+			},
+			Expr::Access { lhs, .. }
+			| Expr::Spanned { expr: lhs, .. } => vec![lhs],
+			Expr::Literal(_)
+			| Expr::Ref(_)
+			| Expr::Import(_)
+			| Expr::Module(_)
+			| Expr::Super(_) => Vec::new(),
+		}
+	}
+
+	/// the name of every binding this expression refers to, which its value depends on
+	/// names bound by blocks inside of it are left out
+	pub fn references(&self) -> Vec<&str> {
+		let mut out: Vec<&str> = Vec::new();
+		let mut stack: Vec<(&Expr, Vec<&str>)> = vec![(self, Vec::new())];
+
+		while let Some((expr, locals)) = stack.pop() {
+			match expr {
+				Expr::Ref(name) if !locals.contains(&name.as_str()) && !out.contains(&name.as_str()) => out.push(name),
+				// each local is only in scope for the ones after it and the body
+				Expr::Block { locals: bindings, body } => {
+					let mut scope = locals.clone();
+
+					for (name, value) in bindings {
+						stack.push((value, scope.clone()));
+						scope.push(name);
+					}
+
+					stack.push((body, scope));
+				},
+				expr => stack.extend(expr.children().into_iter().map(|e| (e, locals.clone()))),
+			}
+		}
+
+		out
+	}
+
+	/// every expression directly inside of this one, mutably
+	pub fn children_mut(&mut self) -> Vec<&mut Expr> {
+		match self {
+			Expr::Literal(Literal::Array(e)) => e.iter_mut().collect(),
+			Expr::Literal(Literal::Record(e))
+			| Expr::Scope(e) => e.values_mut().collect(),
+			Expr::FnCall { args, .. }
+			| Expr::Interpolation(args) => args.iter_mut().collect(),
+			Expr::PrefixOp { expr, .. } => vec![expr],
+			Expr::InfixOp { lhs, rhs, .. } => vec![lhs, rhs],
+			Expr::Conditional { condition, then_block, else_block } => vec![condition, then_block, else_block],
+			Expr::ArrayAccess { lhs, index } => vec![lhs, index],
+			Expr::Block { locals, body } => {
+				let mut out: Vec<&mut Expr> = locals.iter_mut().map(|(_, e)| e).collect();
+				out.push(body);
+
+				out
+			},
+			Expr::Access { lhs, .. }
+			| Expr::Spanned { expr: lhs, .. } => vec![lhs],
+			Expr::Literal(_)
+			| Expr::Ref(_)
+			| Expr::Import(_)
+			| Expr::Module(_)
+			| Expr::Super(_) => Vec::new(),
+		}
+	}
 }
 
 impl From<String> for Expr {
@@ -85,46 +396,231 @@ impl From<Vec<Expr>> for Expr {
 	}
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConstraintKind {
+	/// `in 1..=65535`, the value must be in the range or array
+	In,
+	/// `where len(name) > 0`, the expression must evaluate to `true`
+	Where,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraint {
+	pub kind: ConstraintKind,
+	pub expr: Expr,
+	/// the expression as it was written, for error messages
+	pub source: String,
+}
+
+impl fmt::Display for Constraint {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self.kind {
+			ConstraintKind::In => write!(f, "in {}", self.source),
+			ConstraintKind::Where => write!(f, "where {}", self.source),
+		}
+	}
+}
+
+#[derive(Debug, PartialEq)]
 pub struct Input {
 	pub static_type: InputType,
+	pub constraints: Vec<Constraint>,
 	pub default: Option<Expr>,
 	pub current: Option<Literal>,
 }
 
+/// `export binding as name;`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Output {
+	pub name: String,
+	pub binding: String,
+}
+
 #[derive(Debug)]
 pub struct Store {
 	pub contents: HashMap<String, Expr>,
 	pub inputs: HashMap<String, Input>,
-	pub outputs: Vec<String>,
+	pub outputs: Vec<Output>,
+	/// every file imported by this one, by id
+	pub modules: HashMap<String, Store>,
+	/// where each binding's name was written, including bindings made by imports
+	pub locations: HashMap<String, Location>,
+	/// the `///` comments before each binding, by the binding's name
+	pub docs: HashMap<String, String>,
+	/// where each input was imported, by the name the host sets
+	pub input_locations: HashMap<String, Location>,
+	/// where each export was written, by the name it's exported as
+	pub output_locations: HashMap<String, Location>,
+	/// what each `super.name` in a layer refers to, under a name that can't be written in a file
+	pub(crate) supers: HashMap<String, Expr>,
 }
 
 impl Store {
 	pub fn new() -> Store {
 		let contents: HashMap<String, Expr> = HashMap::new();
 		let inputs: HashMap<String, Input> = HashMap::new();
-		let outputs: Vec<String> = Vec::new();
-		
-		
-		Store { contents, inputs, outputs }
+		let outputs: Vec<Output> = Vec::new();
+		let modules: HashMap<String, Store> = HashMap::new();
+		let locations: HashMap<String, Location> = HashMap::new();
+		let docs: HashMap<String, String> = HashMap::new();
+		let input_locations: HashMap<String, Location> = HashMap::new();
+		let output_locations: HashMap<String, Location> = HashMap::new();
+		let supers: HashMap<String, Expr> = HashMap::new();
+
+		Store { contents, inputs, outputs, modules, locations, docs, input_locations, output_locations, supers }
 	}
 
-	pub fn insert<T: Into<String>>(&mut self, key: T, expr: Expr) -> Result<Option<Expr>, NameConflictError> {
+	/// puts `layer` on top of this store, replacing any bindings, input defaults and exports with the same name
+	/// `super.name` in the layer refers to what `name` was before the layer was added
+	/// the layer is parsed on its own, so it can only export its own bindings, but `name = super.name` makes one of the base's its own
+	pub fn overlay(mut self, mut layer: Store) -> Result<Store, UndefinedError> {
+		let layer_id = LAYERS.fetch_add(1, Ordering::Relaxed);
+
+		let mut supers: HashMap<String, String> = HashMap::new();
+		let reimported: Vec<String> = layer.inputs.keys().cloned().collect();
+
+		for expr in layer.contents.values_mut().chain(layer.inputs.values_mut().filter_map(|e| e.default.as_mut())) {
+			let mut stack = vec![expr];
+
+			while let Some(expr) = stack.pop() {
+				// one the layer's own `overlay` already pointed somewhere is left alone
+				if let Expr::Super(name) = expr {
+					if layer.supers.contains_key(name) {
+						continue;
+					}
+
+					let hidden = match supers.get(name) {
+						Some(e) => e.to_owned(),
+						None => {
+							let hidden = format!("{}@{}", name, layer_id);
+							let value = self.super_value(name, &reimported)?;
+
+							self.supers.insert(hidden.clone(), value);
+							supers.insert(name.to_owned(), hidden.clone());
+
+							hidden
+						},
+					};
+
+					*expr = Expr::Super(hidden);
+					continue;
+				}
+
+				stack.extend(expr.children_mut());
+			}
+		}
+
+		for (name, input) in layer.inputs {
+			match self.inputs.get_mut(&name) {
+				Some(base) => {
+					if input.static_type != InputType::Any {
+						base.static_type = input.static_type;
+					}
+
+					base.constraints.extend(input.constraints);
+					base.default = input.default.or(base.default.take());
+					base.current = input.current.or(base.current.take());
+				},
+				None => {
+					self.inputs.insert(name, input);
+				},
+			}
+		}
+
+		self.contents.extend(layer.contents);
+		self.supers.extend(layer.supers);
+		self.modules.extend(layer.modules);
+		self.locations.extend(layer.locations);
+		self.docs.extend(layer.docs);
+		self.input_locations.extend(layer.input_locations);
+		self.output_locations.extend(layer.output_locations);
+
+		for output in layer.outputs {
+			match self.outputs.iter_mut().find(|e| e.name == output.name) {
+				Some(e) => *e = output,
+				None => self.outputs.push(output),
+			}
+		}
+
+		Ok(self)
+	}
+
+	/// what `super.name` means for a layer that imports the inputs in `reimported`
+	fn super_value(&self, name: &str, reimported: &[String]) -> Result<Expr, UndefinedError> {
+		match self.contents.get(name).map(Expr::inner) {
+			// an input the layer imports again is replaced along with its default,
+			// so `super` has to refer to the default itself
+			Some(Expr::Import(input)) if reimported.contains(input) => {
+				let default = self.inputs.get(input).and_then(|e| e.default.clone());
+
+				Ok(default.unwrap_or(Expr::Literal(Literal::None)))
+			},
+			Some(_) => Ok(self.contents[name].clone()),
+			None => Err(UndefinedError::new(name, "super")),
+		}
+	}
+
+	/// `location` is where the name was written, which a later conflicting definition points back to
+	pub fn insert<T: Into<String>>(&mut self, key: T, expr: Expr, location: Option<Location>) -> Result<Option<Expr>, NameConflictError> {
 		let key = key.into();
 
 		if self.contents.contains_key(&key) {
-			return Err(NameConflictError::new(key, "global"));
+			let first = self.locations.get(&key).cloned();
+			return Err(NameConflictError::new(key, "global").with_locations(first, location));
+		}
+
+		if let Some(location) = location {
+			self.locations.insert(key.clone(), location);
 		}
 
 		Ok(self.contents.insert(key, expr))
 	}
 
-	pub fn insert_input<T: Into<String>>(&mut self, key: T, input: Input) -> Result<Option<Input>, NameConflictError> {
-		let key = key.into();
+	/// `name` is what the host sets, `binding` is what the rest of the file refers to it as
+	pub fn insert_input<T, E>(&mut self, name: T, binding: E, input: Input, location: Option<Location>) -> Result<Option<Input>, NameConflictError>
+	where
+	String: From<T>,
+	String: From<E> {
+		let name = String::from(name);
+
+		if self.inputs.contains_key(&name) {
+			let first = self.input_locations.get(&name).cloned();
+			return Err(NameConflictError::new(name, "inputs").with_locations(first, location));
+		}
+
+		self.insert(binding, Expr::Import(name.clone()), location.clone())?;
 
-		self.insert(key, Expr::Import)?;
+		if let Some(location) = location {
+			self.input_locations.insert(name.clone(), location);
+		}
 
-		Ok(self.inputs.insert(key, input))
+		Ok(self.inputs.insert(name, input))
+	}
+
+	pub fn insert_output<T, E>(&mut self, name: T, binding: E, location: Option<Location>) -> Result<(), NameConflictError>
+	where
+	String: From<T>,
+	String: From<E> {
+		let name = String::from(name);
+		let binding = String::from(binding);
+
+		if self.outputs.iter().any(|output| output.name == name) {
+			let first = self.output_locations.get(&name).cloned();
+			return Err(NameConflictError::new(name, "outputs").with_locations(first, location));
+		}
+
+		if let Some(location) = location {
+			self.output_locations.insert(name.clone(), location);
+		}
+
+		self.outputs.push(Output { name, binding });
+
+		Ok(())
+	}
+
+	/// the documentation of a binding, from the `///` comments before it
+	pub fn docs<T: AsRef<str>>(&self, name: T) -> Option<&str> {
+		self.docs.get(name.as_ref()).map(|e| e.as_str())
 	}
 
 	#[cfg(test)]
@@ -132,4 +628,10 @@ impl Store {
 		let key = key.into();
 		self.contents.get(&key)
 	}
-}
\ No newline at end of file
+}
+
+impl Default for Store {
+	fn default() -> Store {
+		Store::new()
+	}
+}
@@ -0,0 +1,207 @@
+use std::{fmt, ops::Range};
+
+use crate::lexer::Token;
+
+/// what a node in the syntax tree is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+	/// a whole file
+	File,
+	/// `name = value;`
+	Binding,
+	/// `import a, b as c: int = 5;`
+	Import,
+	/// one of the inputs of an import, like `b as c: int = 5`
+	Input,
+	/// a type annotation, like `int` or `[string]?`
+	Type,
+	/// `in range` or `where condition`
+	Constraint,
+	/// `import "path" as name;`
+	ModuleImport,
+	/// `from "path" import a, b as c;`
+	FromImport,
+	/// `export a, b as c;` or `export a = 5;`
+	Export,
+	/// a statement that didn't parse, up to where parsing picked back up
+	Error,
+	Literal,
+	Ref,
+	Super,
+	/// `(expr)`
+	Paren,
+	Prefix,
+	Infix,
+	Call,
+	Array,
+	/// `lhs[index]`
+	Index,
+	/// `lhs.field`
+	Access,
+	Conditional,
+	Block,
+	/// `name := value` inside of a block
+	Local,
+}
+
+/// a node or a token in the syntax tree
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyntaxElement<'a> {
+	Node(SyntaxNode<'a>),
+	Token(Token<'a>),
+}
+
+/// a node of the lossless syntax tree, which keeps every token of the source, comments and whitespace included
+/// the `Expr` of an expression is derived from its node, so tools can change the source without losing its formatting
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode<'a> {
+	pub kind: SyntaxKind,
+	pub children: Vec<SyntaxElement<'a>>,
+}
+
+impl<'a> SyntaxNode<'a> {
+	/// the nodes directly inside of this one
+	pub fn nodes(&self) -> impl Iterator<Item = &SyntaxNode<'a>> {
+		self.children.iter().filter_map(|e| match e {
+			SyntaxElement::Node(e) => Some(e),
+			SyntaxElement::Token(_) => None,
+		})
+	}
+
+	/// the tokens directly inside of this one, leaving out comments and whitespace
+	pub fn tokens(&self) -> impl Iterator<Item = &Token<'a>> {
+		self.children.iter().filter_map(|e| match e {
+			SyntaxElement::Token(e) if !e.kind.is_trivia() => Some(e),
+			_ => None,
+		})
+	}
+
+	/// the first token in the node that isn't trivia
+	pub fn first_token(&self) -> Option<&Token<'a>> {
+		self.children.iter().find_map(|e| match e {
+			SyntaxElement::Node(e) => e.first_token(),
+			SyntaxElement::Token(e) if !e.kind.is_trivia() => Some(e),
+			SyntaxElement::Token(_) => None,
+		})
+	}
+
+	/// the last token in the node that isn't trivia
+	pub fn last_token(&self) -> Option<&Token<'a>> {
+		self.children.iter().rev().find_map(|e| match e {
+			SyntaxElement::Node(e) => e.last_token(),
+			SyntaxElement::Token(e) if !e.kind.is_trivia() => Some(e),
+			SyntaxElement::Token(_) => None,
+		})
+	}
+
+	/// the byte offsets of the node in the source, from its first token to its last, leaving out trivia at either end
+	pub fn span(&self) -> Option<Range<usize>> {
+		Some(self.first_token()?.span.start..self.last_token()?.span.end)
+	}
+}
+
+/// writes the source the node was parsed from, exactly as it was
+impl fmt::Display for SyntaxNode<'_> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		for e in &self.children {
+			match e {
+				SyntaxElement::Node(e) => write!(f, "{}", e)?,
+				SyntaxElement::Token(e) => write!(f, "{}", e.slice)?,
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// collects the tokens the parser consumes, wrapping them into nodes as they're parsed
+#[derive(Debug, Default)]
+pub(crate) struct Builder<'a> {
+	elements: Vec<SyntaxElement<'a>>,
+	/// the trivia before the next token, which goes in whichever node that token does
+	trivia: Vec<Token<'a>>,
+}
+
+impl<'a> Builder<'a> {
+	/// keeps a comment or some whitespace, to add along with the next token
+	pub(crate) fn trivia(&mut self, token: Token<'a>) {
+		self.trivia.push(token);
+	}
+
+	pub(crate) fn token(&mut self, token: Token<'a>) {
+		self.flush();
+		self.elements.push(SyntaxElement::Token(token));
+	}
+
+	/// where a node starting at the next token would start, to pass to `wrap`
+	pub(crate) fn checkpoint(&mut self) -> usize {
+		self.flush();
+		self.elements.len()
+	}
+
+	/// wraps everything added since `checkpoint` into a node
+	pub(crate) fn wrap(&mut self, checkpoint: usize, kind: SyntaxKind) {
+		if checkpoint == self.elements.len() {
+			return;
+		}
+
+		let children = self.elements.split_off(checkpoint);
+		self.elements.push(SyntaxElement::Node(SyntaxNode { kind, children }));
+	}
+
+	/// the node `wrap` made last, if nothing has been added since
+	pub(crate) fn last(&self) -> Option<&SyntaxNode<'a>> {
+		match self.elements.last() {
+			Some(SyntaxElement::Node(e)) => Some(e),
+			_ => None,
+		}
+	}
+
+	/// the whole file, with the trivia at the end of it
+	pub(crate) fn finish(mut self) -> SyntaxNode<'a> {
+		self.flush();
+
+		SyntaxNode { kind: SyntaxKind::File, children: self.elements }
+	}
+
+	fn flush(&mut self) {
+		self.elements.extend(self.trivia.drain(..).map(SyntaxElement::Token));
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::{lexer::tokens::TokenKind, parser::syntax_tree};
+
+	use super::SyntaxKind;
+
+	#[test]
+	fn lossless() {
+		let source = "/// the answer\nnice = 23 + ( 7 * cool ) // why\n\nimport port: int in 1..5, /* host */ host\n\n/* broken */ oops = + ;\nlast = if a { b := 1; b } else { 2 }\n";
+		let (tree, errors) = syntax_tree(source);
+
+		assert_eq!(errors.len(), 1);
+		assert_eq!(tree.to_string(), source);
+
+		let kinds = tree.nodes().map(|e| e.kind).collect::<Vec<SyntaxKind>>();
+		assert_eq!(kinds, vec![SyntaxKind::Binding, SyntaxKind::Import, SyntaxKind::Error, SyntaxKind::Binding]);
+	}
+
+	#[test]
+	fn nodes() {
+		let source = "nice = 23 + (7 * cool);";
+		let (tree, _) = syntax_tree(source);
+
+		let binding = tree.nodes().next().unwrap();
+		assert_eq!(binding.span(), Some(0..23));
+
+		let value = binding.nodes().next().unwrap();
+		assert_eq!(value.kind, SyntaxKind::Infix);
+		assert_eq!(value.tokens().map(|e| e.kind).collect::<Vec<TokenKind>>(), vec![TokenKind::Add]);
+
+		let paren = value.nodes().nth(1).unwrap();
+		assert_eq!(paren.kind, SyntaxKind::Paren);
+		assert_eq!(paren.to_string(), "(7 * cool)");
+		assert_eq!(paren.nodes().next().unwrap().kind, SyntaxKind::Infix);
+	}
+}
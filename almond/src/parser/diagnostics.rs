@@ -0,0 +1,332 @@
+use std::{fmt::Write, ops::Range};
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// converts byte offsets into a source file to lines and columns
+#[derive(Debug, Clone)]
+pub struct SourceMap<'a> {
+	name: Option<&'a str>,
+	source: &'a str,
+	/// the byte offset each line starts at
+	lines: Vec<usize>,
+}
+
+impl<'a> SourceMap<'a> {
+	pub fn new(source: &'a str) -> SourceMap<'a> {
+		let name = None;
+		let lines = std::iter::once(0)
+			.chain(source.match_indices('\n').map(|(i, _)| i + 1))
+			.collect();
+
+		SourceMap { name, source, lines }
+	}
+
+	/// a source map for a file, which names it in rendered diagnostics
+	pub fn named(name: &'a str, source: &'a str) -> SourceMap<'a> {
+		let mut map = SourceMap::new(source);
+		map.name = Some(name);
+
+		map
+	}
+
+	/// the line and column of `offset`, both starting at 1
+	/// columns count characters, not bytes, and an offset past the end of the source is at its end
+	pub fn location(&self, offset: usize) -> (usize, usize) {
+		let offset = self.clamp(offset);
+		let line = self.lines.partition_point(|start| *start <= offset) - 1;
+		let column = self.source[self.lines[line]..offset].chars().count();
+
+		(line + 1, column + 1)
+	}
+
+	/// the text of a line, starting at 1, without its line break
+	pub fn line(&self, line: usize) -> &'a str {
+		let start = self.lines[line - 1];
+		let end = self.lines.get(line).map_or(self.source.len(), |e| e - 1);
+
+		self.source[start..end].trim_end_matches('\r')
+	}
+
+	/// `offset` moved back into the source, onto the start of the character it's in
+	fn clamp(&self, offset: usize) -> usize {
+		let mut offset = offset.min(self.source.len());
+
+		while !self.source.is_char_boundary(offset) {
+			offset -= 1;
+		}
+
+		offset
+	}
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+	pub span: Range<usize>,
+	pub message: String,
+}
+
+impl Label {
+	pub fn new<T: Into<String>>(span: Range<usize>, message: T) -> Label {
+		let message = message.into();
+
+		Label { span, message }
+	}
+}
+
+/// an error ready to be shown to a user, pointing at the source it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+	/// the code of the error, like `A0001`
+	pub code: Option<&'static str>,
+	pub message: String,
+	/// where the error happened
+	pub primary: Option<Label>,
+	/// other places that explain the error, like an earlier definition
+	pub secondary: Vec<Label>,
+	/// the id of the file the labels point into, if it came from a resolver
+	pub file: Option<String>,
+}
+
+impl Diagnostic {
+	pub fn new<T: Into<String>>(message: T) -> Diagnostic {
+		let message = message.into();
+
+		Diagnostic { code: None, message, primary: None, secondary: Vec::new(), file: None }
+	}
+
+	pub fn with_code(mut self, code: &'static str) -> Diagnostic {
+		self.code = Some(code);
+		self
+	}
+
+	pub fn with_primary(mut self, label: Label) -> Diagnostic {
+		self.primary = Some(label);
+		self
+	}
+
+	pub fn with_secondary(mut self, label: Label) -> Diagnostic {
+		self.secondary.push(label);
+		self
+	}
+
+	pub fn with_file(mut self, file: Option<String>) -> Diagnostic {
+		self.file = file;
+		self
+	}
+
+	/// renders the message with each labelled line of source underneath it, underlined,
+	/// using ANSI escape codes if `color` is set
+	pub fn render(&self, map: &SourceMap, color: bool) -> String {
+		let paint = |code: &'static str| if color { code } else { "" };
+		let reset = paint(RESET);
+
+		let mut labels: Vec<(&Label, bool)> = self.primary.iter().map(|e| (e, true))
+			.chain(self.secondary.iter().map(|e| (e, false)))
+			.collect();
+		labels.sort_by_key(|(label, _)| label.span.start);
+
+		let last_line = labels.iter().map(|(label, _)| map.location(label.span.start).0).max().unwrap_or(1);
+		let gutter = " ".repeat(last_line.to_string().len());
+
+		let mut out = String::new();
+		let code = self.code.map(|e| format!("[{}]", e)).unwrap_or_default();
+		let _ = writeln!(out, "{}error{}{}: {}{}{}", paint(RED), code, reset, paint(BOLD), self.message, reset);
+
+		// the labels point into a file other than the one in `map`, like a module it imports, so only the file is named
+		if let (Some(file), Some(name)) = (&self.file, map.name) {
+			if file != name {
+				let _ = writeln!(out, " {}-->{} {}", paint(BLUE), reset, file);
+				return out;
+			}
+		}
+
+		if let Some(label) = self.primary.as_ref().or(labels.first().map(|(label, _)| *label)) {
+			let (line, column) = map.location(label.span.start);
+
+			let location = match map.name {
+				Some(name) => format!("{}:{}:{}", name, line, column),
+				None => format!("{}:{}", line, column),
+			};
+
+			let _ = writeln!(out, "{}{}-->{} {}", gutter, paint(BLUE), reset, location);
+		}
+
+		if labels.is_empty() {
+			return out;
+		}
+
+		let _ = writeln!(out, "{} {}|{}", gutter, paint(BLUE), reset);
+		let mut previous_line = None;
+
+		for (label, primary) in labels {
+			let (line, column) = map.location(label.span.start);
+			let text = map.line(line);
+
+			if previous_line != Some(line) {
+				let _ = writeln!(out, "{}{:>width$} |{} {}", paint(BLUE), line, reset, text, width = gutter.len());
+				previous_line = Some(line);
+			}
+
+			// tabs are kept so the underline lines up however wide they're shown
+			let indent: String = text.chars()
+				.take(column - 1)
+				.map(|e| if e == '\t' { '\t' } else { ' ' })
+				.collect();
+
+			// only the part of the span on its first line is underlined, and empty spans still get one mark
+			let start = map.clamp(label.span.start);
+			let line_end = map.lines.get(line).map_or(map.source.len(), |e| e - 1);
+			let end = map.clamp(label.span.end).clamp(start, line_end);
+			let width = map.source[start..end].chars().count().max(1);
+
+			let (mark, code) = match primary {
+				true => ("^", paint(RED)),
+				false => ("-", paint(BLUE)),
+			};
+
+			let _ = writeln!(
+				out,
+				"{} {}|{} {}{}{} {}{}",
+				gutter, paint(BLUE), reset, indent, code, mark.repeat(width), label.message, reset
+			);
+		}
+
+		out
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::parser::{eval, eval_file, errors::AlmondError, modules::MemoryResolver};
+
+	use super::{SourceMap, Diagnostic, Label};
+
+	#[test]
+	fn locations() {
+		let map = SourceMap::new("nice = 1;\ncool = \"é\" + 2;\n");
+
+		assert_eq!(map.location(0), (1, 1));
+		assert_eq!(map.location(7), (1, 8));
+		assert_eq!(map.location(10), (2, 1));
+		assert_eq!(map.location(20), (2, 10));
+		assert_eq!(map.location(27), (3, 1));
+		assert_eq!(map.line(2), "cool = \"é\" + 2;");
+
+		// past the end of the source, or in the middle of `é`
+		assert_eq!(map.location(100), (3, 1));
+		assert_eq!(map.location(19), (2, 9));
+	}
+
+	#[test]
+	fn render_syntax_error() {
+		let source = "nice = 4;\ncool = (nice + 2;\n";
+		let err = eval(source).unwrap_err();
+
+		assert_eq!(
+			err.diagnostic().render(&SourceMap::named("main.almond", source), false),
+			concat!(
+				"error[A0001]: SyntaxError: Unexpected token `;`\n",
+				" --> main.almond:2:17\n",
+				"  |\n",
+				"2 | cool = (nice + 2;\n",
+				"  |                 ^ expected `)`\n",
+			)
+		);
+	}
+
+	#[test]
+	fn render_imported() {
+		let main = "import \"lib.almond\" as lib;";
+		let lib = "a = 1;\nb = 2;\nc = 3;\nnice = (1;\n";
+
+		let mut resolver = MemoryResolver::new();
+		resolver.insert("main.almond", main).unwrap();
+		resolver.insert("lib.almond", lib).unwrap();
+
+		let diagnostic = eval_file("main.almond", &resolver).unwrap_err().diagnostic();
+
+		assert_eq!(diagnostic.file.as_deref(), Some("lib.almond"));
+		assert_eq!(
+			diagnostic.render(&SourceMap::named("main.almond", main), false),
+			"error[A0001]: SyntaxError: Unexpected token `;`\n --> lib.almond\n"
+		);
+		assert_eq!(
+			diagnostic.render(&SourceMap::named("lib.almond", lib), false),
+			concat!(
+				"error[A0001]: SyntaxError: Unexpected token `;`\n",
+				" --> lib.almond:4:10\n",
+				"  |\n",
+				"4 | nice = (1;\n",
+				"  |          ^ expected `)`\n",
+			)
+		);
+
+		// a map that doesn't say which file it's for is used as is, with the spans kept inside of it
+		assert!(diagnostic.render(&SourceMap::new(main), false).contains(" --> 1:28\n"));
+	}
+
+	#[test]
+	fn render_located() {
+		let source = "nice = 1;\nexport nice, missing;";
+		let err = eval(source).unwrap_err();
+
+		assert_eq!(
+			err.diagnostic().render(&SourceMap::new(source), false),
+			concat!(
+				"error[A0003]: UndefinedError: Member with name `missing` does not exist in scope `global`\n",
+				" --> 2:14\n",
+				"  |\n",
+				"2 | export nice, missing;\n",
+				"  |              ^^^^^^^ not defined\n",
+			)
+		);
+
+		let source = "import port: int = \"80\", size: int in 1..5 = 10, count: int;";
+		let store = eval(source).unwrap();
+
+		let rendered = |name: &str| store.get_input(name).unwrap_err().diagnostic().render(&SourceMap::new(source), false);
+
+		assert!(rendered("port").ends_with("1 | import port: int = \"80\", size: int in 1..5 = 10, count: int;\n  |                    ^^^^ expected `int`\n"), "{}", rendered("port"));
+		assert!(rendered("size").contains(" --> 1:46\n"), "{}", rendered("size"));
+		assert!(rendered("size").ends_with(" ^^ doesn't satisfy `in 1..5`\n"), "{}", rendered("size"));
+		assert!(rendered("count").contains(" --> 1:50\n"), "{}", rendered("count"));
+	}
+
+	#[test]
+	fn render_secondary() {
+		let source = "\tnice = 4;\n\tnice = 5;";
+		let diagnostic = Diagnostic::new("NameConflictError: `nice` is defined twice")
+			.with_primary(Label::new(12..16, "redefined here"))
+			.with_secondary(Label::new(1..5, "first defined here"));
+
+		assert_eq!(
+			diagnostic.render(&SourceMap::new(source), false),
+			concat!(
+				"error: NameConflictError: `nice` is defined twice\n",
+				" --> 2:2\n",
+				"  |\n",
+				"1 | \tnice = 4;\n",
+				"  | \t---- first defined here\n",
+				"2 | \tnice = 5;\n",
+				"  | \t^^^^ redefined here\n",
+			)
+		);
+
+		let err = eval(source).unwrap_err();
+		assert_eq!(err.diagnostic().render(&SourceMap::new(source), false), concat!(
+			"error[A0002]: NameConflictError: `nice` is defined twice in scope `global`\n",
+			" --> 2:2\n",
+			"  |\n",
+			"1 | \tnice = 4;\n",
+			"  | \t---- first defined here\n",
+			"2 | \tnice = 5;\n",
+			"  | \t^^^^ defined again here\n",
+		));
+
+		let colored = diagnostic.render(&SourceMap::new(source), true);
+		assert!(colored.starts_with("\x1b[1;31merror\x1b[0m: "));
+	}
+}
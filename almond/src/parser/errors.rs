@@ -4,12 +4,23 @@ use logos::Span;
 
 use crate::lexer::tokens::TokenKind;
 
-pub trait AlmondError: Clone + fmt::Display {}
+use super::{ast::{InputType, Literal, Location}, diagnostics::{Diagnostic, Label}};
+
+pub trait AlmondError: Clone + fmt::Display {
+	/// the error as a diagnostic that can be rendered with the source it came from
+	fn diagnostic(&self) -> Diagnostic {
+		Diagnostic::new(self.to_string())
+	}
+}
 
 #[derive(Debug, Clone)]
 pub struct NameConflictError {
 	name: String,
-	scope: String
+	scope: String,
+	/// where the name was first defined, boxed to keep results small
+	first: Option<Box<Location>>,
+	/// where it was defined again
+	second: Option<Box<Location>>,
 }
 
 impl NameConflictError {
@@ -20,7 +31,21 @@ impl NameConflictError {
 		let name = name.into();
 		let scope = scope.into();
 		
-		NameConflictError { name, scope }
+		NameConflictError { name, scope, first: None, second: None }
+	}
+
+	pub fn with_locations(mut self, first: Option<Location>, second: Option<Location>) -> NameConflictError {
+		self.first = first.map(Box::new);
+		self.second = second.map(Box::new);
+		self
+	}
+
+	pub fn first(&self) -> Option<&Location> {
+		self.first.as_deref()
+	}
+
+	pub fn second(&self) -> Option<&Location> {
+		self.second.as_deref()
 	}
 }
 
@@ -30,24 +55,529 @@ impl fmt::Display for NameConflictError {
 	}
 }
 
-impl AlmondError for NameConflictError {}
+impl AlmondError for NameConflictError {
+	fn diagnostic(&self) -> Diagnostic {
+		let mut diagnostic = Diagnostic::new(format!("NameConflictError: `{}` is defined twice in scope `{}`", self.name, self.scope));
+
+		if let Some(second) = &self.second {
+			diagnostic = diagnostic
+				.with_primary(Label::new(second.span.clone(), "defined again here"))
+				.with_file(second.file.clone());
+		}
+
+		// the first definition can be in another file when layering, so it's only shown if it's in the same one
+		match (&self.first, &self.second) {
+			(Some(first), Some(second)) if first.file == second.file => {
+				diagnostic.with_secondary(Label::new(first.span.clone(), "first defined here"))
+			},
+			(Some(first), None) => diagnostic.with_secondary(Label::new(first.span.clone(), "first defined here")),
+			_ => diagnostic,
+		}
+	}
+}
 
 #[derive(Debug, Clone)]
 pub struct SyntaxError {
 	found: TokenKind,
-	at: Span
+	at: Span,
+	expected: Vec<TokenKind>,
+	/// the id of the file it's in, if it came from a resolver
+	file: Option<String>,
+}
+
+impl SyntaxError {
+	pub fn new(found: TokenKind, at: Span, expected: Vec<TokenKind>) -> SyntaxError {
+		SyntaxError { found, at, expected, file: None }
+	}
+
+	pub fn with_file(mut self, file: Option<String>) -> SyntaxError {
+		self.file = file;
+		self
+	}
 }
 
 impl SyntaxError {
-	pub fn new(found: TokenKind, at: Span) -> SyntaxError {
-		SyntaxError { found, at }
+	/// "expected `a`, `b` or `c`"
+	fn expected(&self) -> String {
+		let mut out = String::new();
+
+		for (i, expected) in self.expected.iter().enumerate() {
+			match i {
+				0 => out += &format!("expected `{}`", expected),
+				_ if i == self.expected.len() - 1 => out += &format!(" or `{}`", expected),
+				_ => out += &format!(", `{}`", expected),
+			}
+		}
+
+		out
 	}
 }
 
 impl fmt::Display for SyntaxError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		write!(f, "SyntaxError: Unexpected token `{}` at char `{}`", self.found, self.at.next().unwrap())
+		write!(f, "SyntaxError: Unexpected token `{}` at char `{}`", self.found, self.at.start)?;
+
+		if !self.expected.is_empty() {
+			write!(f, ", {}", self.expected())?;
+		}
+
+		Ok(())
 	}
 }
 
-impl AlmondError for SyntaxError {}
\ No newline at end of file
+impl AlmondError for SyntaxError {
+	fn diagnostic(&self) -> Diagnostic {
+		let label = match self.expected.is_empty() {
+			true => "unexpected token".to_owned(),
+			false => self.expected(),
+		};
+
+		Diagnostic::new(format!("SyntaxError: Unexpected token `{}`", self.found))
+			.with_primary(Label::new(self.at.clone(), label))
+			.with_file(self.file.clone())
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct LexError {
+	slice: String,
+	at: Span,
+	reason: String,
+	/// the id of the file it's in, if it came from a resolver
+	file: Option<String>,
+}
+
+impl LexError {
+	pub fn new<T, E>(slice: T, at: Span, reason: E) -> LexError
+	where
+	String: From<T>,
+	String: From<E> {
+		let slice = slice.into();
+		let reason = reason.into();
+
+		LexError { slice, at, reason, file: None }
+	}
+
+	pub fn with_file(mut self, file: Option<String>) -> LexError {
+		self.file = file;
+		self
+	}
+}
+
+impl fmt::Display for LexError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "LexError: Invalid token `{}` at char `{}`: {}", self.slice, self.at.start, self.reason)
+	}
+}
+
+impl AlmondError for LexError {
+	fn diagnostic(&self) -> Diagnostic {
+		Diagnostic::new(format!("LexError: Invalid token `{}`", self.slice))
+			.with_primary(Label::new(self.at.clone(), self.reason.clone()))
+			.with_file(self.file.clone())
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct ResolveError {
+	path: String,
+	reason: String
+}
+
+impl ResolveError {
+	pub fn new<T, E>(path: T, reason: E) -> ResolveError
+	where
+	String: From<T>,
+	String: From<E> {
+		let path = path.into();
+		let reason = reason.into();
+
+		ResolveError { path, reason }
+	}
+}
+
+impl fmt::Display for ResolveError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "ResolveError: Couldn't import `{}`: {}", self.path, self.reason)
+	}
+}
+
+impl AlmondError for ResolveError {}
+
+#[derive(Debug, Clone)]
+pub struct UndefinedError {
+	name: String,
+	scope: String,
+	/// where the name was used, like in an `export`, boxed to keep results small
+	location: Option<Box<Location>>,
+}
+
+impl UndefinedError {
+	pub fn new<T, E>(name: T, scope: E) -> UndefinedError
+	where
+	String: From<T>,
+	String: From<E> {
+		let name = name.into();
+		let scope = scope.into();
+
+		UndefinedError { name, scope, location: None }
+	}
+
+	pub fn with_location(mut self, location: Option<Location>) -> UndefinedError {
+		self.location = location.map(Box::new);
+		self
+	}
+}
+
+impl fmt::Display for UndefinedError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "UndefinedError: Member with name `{}` does not exist in scope `{}`", self.name, self.scope)
+	}
+}
+
+impl AlmondError for UndefinedError {
+	fn diagnostic(&self) -> Diagnostic {
+		located(Diagnostic::new(self.to_string()), &self.location, "not defined")
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct TypeError {
+	input: String,
+	expected: InputType,
+	found: Literal,
+	/// the default the value came from, or the input if it has none, boxed to keep results small
+	location: Option<Box<Location>>,
+}
+
+impl TypeError {
+	pub fn new<T>(input: T, expected: InputType, found: Literal) -> TypeError
+	where
+	String: From<T> {
+		let input = input.into();
+
+		TypeError { input, expected, found, location: None }
+	}
+
+	pub fn with_location(mut self, location: Option<Location>) -> TypeError {
+		self.location = location.map(Box::new);
+		self
+	}
+}
+
+impl fmt::Display for TypeError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "TypeError: Input `{}` expects a value of type `{}`, but got `{}`", self.input, self.expected, self.found)
+	}
+}
+
+impl AlmondError for TypeError {
+	fn diagnostic(&self) -> Diagnostic {
+		located(Diagnostic::new(self.to_string()), &self.location, format!("expected `{}`", self.expected))
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct ConstraintError {
+	input: String,
+	constraint: String,
+	found: Literal,
+	/// the default the value came from, boxed to keep results small
+	location: Option<Box<Location>>,
+}
+
+impl ConstraintError {
+	pub fn new<T, E>(input: T, constraint: E, found: Literal) -> ConstraintError
+	where
+	String: From<T>,
+	String: From<E> {
+		let input = input.into();
+		let constraint = constraint.into();
+
+		ConstraintError { input, constraint, found, location: None }
+	}
+
+	pub fn with_location(mut self, location: Option<Location>) -> ConstraintError {
+		self.location = location.map(Box::new);
+		self
+	}
+}
+
+impl fmt::Display for ConstraintError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "ConstraintError: Input `{}` must satisfy `{}`, but got `{}`", self.input, self.constraint, self.found)
+	}
+}
+
+impl AlmondError for ConstraintError {
+	fn diagnostic(&self) -> Diagnostic {
+		located(Diagnostic::new(self.to_string()), &self.location, format!("doesn't satisfy `{}`", self.constraint))
+	}
+}
+
+/// `diagnostic` pointing at `location`, if the error has one
+fn located<T: Into<String>>(diagnostic: Diagnostic, location: &Option<Box<Location>>, message: T) -> Diagnostic {
+	match location {
+		Some(location) => diagnostic
+			.with_primary(Label::new(location.span.clone(), message))
+			.with_file(location.file.clone()),
+		None => diagnostic,
+	}
+}
+
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+	name: String,
+	reason: String
+}
+
+impl RuntimeError {
+	pub fn new<T, E>(name: T, reason: E) -> RuntimeError
+	where
+	String: From<T>,
+	String: From<E> {
+		let name = name.into();
+		let reason = reason.into();
+
+		RuntimeError { name, reason }
+	}
+}
+
+impl fmt::Display for RuntimeError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "RuntimeError: Couldn't evaluate `{}`: {}", self.name, self.reason)
+	}
+}
+
+impl AlmondError for RuntimeError {}
+
+#[derive(Debug, Clone)]
+pub struct IoError {
+	path: String,
+	reason: String
+}
+
+impl IoError {
+	pub fn new<T, E>(path: T, reason: E) -> IoError
+	where
+	String: From<T>,
+	String: From<E> {
+		let path = path.into();
+		let reason = reason.into();
+
+		IoError { path, reason }
+	}
+}
+
+impl fmt::Display for IoError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "IoError: Couldn't access `{}`: {}", self.path, self.reason)
+	}
+}
+
+impl AlmondError for IoError {}
+
+/// every error almond can return
+#[derive(Debug, Clone)]
+pub enum Error {
+	Syntax(SyntaxError),
+	Lex(LexError),
+	NameConflict(NameConflictError),
+	Undefined(UndefinedError),
+	Type(TypeError),
+	Constraint(ConstraintError),
+	Resolve(ResolveError),
+	Runtime(RuntimeError),
+	Io(IoError),
+}
+
+impl Error {
+	/// a code for the kind of error that never changes, like `A0001`
+	pub fn code(&self) -> &'static str {
+		match self {
+			Error::Syntax(_) => "A0001",
+			Error::NameConflict(_) => "A0002",
+			Error::Undefined(_) => "A0003",
+			Error::Type(_) => "A0004",
+			Error::Constraint(_) => "A0005",
+			Error::Resolve(_) => "A0006",
+			Error::Runtime(_) => "A0007",
+			Error::Io(_) => "A0008",
+			Error::Lex(_) => "A0009",
+		}
+	}
+
+	/// a longer explanation of this kind of error, the same one `explain` returns for its code
+	pub fn explanation(&self) -> &'static str {
+		explain(self.code()).unwrap_or_default()
+	}
+}
+
+/// every error code along with its explanation
+const EXPLANATIONS: [(&str, &str); 9] = [
+	("A0001", "\
+A syntax error means the file isn't valid almond, so nothing in it could be parsed past that point.
+
+The error points at the first token that couldn't be parsed, and lists what could have come there instead.
+A common cause is a missing `;` at the end of a binding, or an unclosed `(`, `[` or `{`."),
+	("A0002", "\
+A name conflict means the same name was defined twice in one scope.
+
+Bindings, the bindings made by imports and the names of exports each have to be unique,
+and a local bound with `:=` can't shadow a local of an enclosing block.
+Rename one of them, or use `as` to import or export under another name."),
+	("A0003", "\
+An undefined error means a name was used that doesn't exist where it was looked up.
+
+This happens when exporting a binding that was never defined, importing a name a module doesn't export,
+using `super.name` for a name the layer below doesn't have, or setting an input that was never imported."),
+	("A0004", "\
+A type error means the value given for an input doesn't match the type it was declared with.
+
+For example, with `import port: int;` setting `port` to a string fails, and so does using `port` without setting it.
+Ints are accepted where floats are expected, and optional types like `int?` also accept no value."),
+	("A0005", "\
+A constraint error means the value given for an input has the right type, but fails one of its constraints.
+
+`in` constraints require the value to be in a range or array, like `import port: int in 1..=65535;`,
+and `where` constraints require an expression to be `true`, like `import name: string where len(name) > 0;`."),
+	("A0006", "\
+A resolution error means a file named by `import \"path\" as name;` or `from \"path\" import name;` couldn't be loaded.
+
+Paths are relative to the importing file, or to the module root if they start with `/`, and can't leave the root.
+Files can't import each other in a cycle."),
+	("A0007", "\
+A runtime error means a binding couldn't be evaluated.
+
+The most common cause is a binding that refers to itself, directly or through other bindings."),
+	("A0008", "\
+An IO error means a file couldn't be read or written, for example because it doesn't exist
+or because of its permissions."),
+	("A0009", "\
+A lex error means part of the file isn't any kind of token, so it couldn't be split into tokens to parse.
+
+This is usually a number literal that's too large, like an int above `9223372036854775807`,
+or a character that isn't part of the language."),
+];
+
+/// the long-form explanation of an error code like `A0001`
+pub fn explain(code: &str) -> Option<&'static str> {
+	EXPLANATIONS.iter()
+		.find(|(e, _)| e.eq_ignore_ascii_case(code))
+		.map(|(_, explanation)| *explanation)
+}
+
+impl From<SyntaxError> for Error {
+	fn from(other: SyntaxError) -> Error {
+		Error::Syntax(other)
+	}
+}
+
+impl From<LexError> for Error {
+	fn from(other: LexError) -> Error {
+		Error::Lex(other)
+	}
+}
+
+impl From<NameConflictError> for Error {
+	fn from(other: NameConflictError) -> Error {
+		Error::NameConflict(other)
+	}
+}
+
+impl From<UndefinedError> for Error {
+	fn from(other: UndefinedError) -> Error {
+		Error::Undefined(other)
+	}
+}
+
+impl From<TypeError> for Error {
+	fn from(other: TypeError) -> Error {
+		Error::Type(other)
+	}
+}
+
+impl From<ConstraintError> for Error {
+	fn from(other: ConstraintError) -> Error {
+		Error::Constraint(other)
+	}
+}
+
+impl From<ResolveError> for Error {
+	fn from(other: ResolveError) -> Error {
+		Error::Resolve(other)
+	}
+}
+
+impl From<RuntimeError> for Error {
+	fn from(other: RuntimeError) -> Error {
+		Error::Runtime(other)
+	}
+}
+
+impl From<IoError> for Error {
+	fn from(other: IoError) -> Error {
+		Error::Io(other)
+	}
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::Syntax(e) => e.fmt(f),
+			Error::Lex(e) => e.fmt(f),
+			Error::NameConflict(e) => e.fmt(f),
+			Error::Undefined(e) => e.fmt(f),
+			Error::Type(e) => e.fmt(f),
+			Error::Constraint(e) => e.fmt(f),
+			Error::Resolve(e) => e.fmt(f),
+			Error::Runtime(e) => e.fmt(f),
+			Error::Io(e) => e.fmt(f),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
+
+impl AlmondError for Error {
+	fn diagnostic(&self) -> Diagnostic {
+		let diagnostic = match self {
+			Error::Syntax(e) => e.diagnostic(),
+			Error::Lex(e) => e.diagnostic(),
+			Error::NameConflict(e) => e.diagnostic(),
+			Error::Undefined(e) => e.diagnostic(),
+			Error::Type(e) => e.diagnostic(),
+			Error::Constraint(e) => e.diagnostic(),
+			Error::Resolve(e) => e.diagnostic(),
+			Error::Runtime(e) => e.diagnostic(),
+			Error::Io(e) => e.diagnostic(),
+		};
+
+		diagnostic.with_code(self.code())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::parser::eval;
+
+	use super::{explain, Error};
+
+	#[test]
+	fn codes() {
+		let err = eval("nice = (1;").unwrap_err();
+
+		assert!(matches!(err, Error::Syntax(_)));
+		assert_eq!(err.code(), "A0001");
+		assert!(err.explanation().starts_with("A syntax error"));
+
+		let err = eval("nice = 1; nice = 2;").unwrap_err();
+		assert_eq!(err.code(), "A0002");
+
+		assert_eq!(explain("a0006"), explain("A0006"));
+		assert!(explain("A9999").is_none());
+
+		let err: Box<dyn std::error::Error> = Box::new(err);
+		assert!(err.to_string().starts_with("NameConflictError"));
+	}
+}
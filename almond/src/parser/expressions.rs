@@ -1,257 +1,696 @@
-use crate::lexer::tokens::TokenKind;
+use crate::lexer::tokens::{TokenKind, describe_error, is_min_int_magnitude};
 
-use super::{Parser, ast::{Expr, Store, Input, InputType}, errors::{SyntaxError, AlmondError}};
+use super::{Parser, ast::{Expr, Location, Store, Input, InputType, Constraint, ConstraintKind}, cst::SyntaxKind, errors::{SyntaxError, LexError, NameConflictError, Error, UndefinedError, ResolveError}};
 
 impl<'a> Parser<'a> {
-	fn parse_expression(&mut self, binding_power: u8) -> Result<(Expr, bool), SyntaxError> {
-		let mut next_requires_end = true;
-		let mut this_requires_end = true;
+	/// parses an expression into a single node of the syntax tree
+	fn parse_expression(&mut self, binding_power: u8) -> Result<(), Error> {
+		let start = self.cst.checkpoint();
 
-		let mut lhs = match self.peek().unwrap_or(TokenKind::EOF) {
+		let kind = match self.peek().unwrap_or(TokenKind::EOF) {
 			TokenKind::Ident => {
-				self.consume(TokenKind::Ident);
-				
-				Expr::Ref(self.slice().to_owned())
-			},
-			TokenKind::String => {
-				self.consume(TokenKind::String);
-
-				let slice = self.slice();
-				let value = &slice[1..slice.len()-1];
-
-				Expr::from(value.to_owned())
-			},
-			TokenKind::Int(e) => {
-				self.consume(TokenKind::Int(e));
-
-				Expr::from(e)
-			},
-			TokenKind::Float(e) => {
-				self.consume(TokenKind::Float(e));
-
-				Expr::from(e)
+				self.consume(TokenKind::Ident)?;
+
+				match self.peek() {
+					Some(TokenKind::LParen) if self.newline_before_peek() => return Err(self.ambiguous().into()),
+					Some(TokenKind::LParen) => {
+						self.parse_call()?;
+						SyntaxKind::Call
+					},
+					_ => SyntaxKind::Ref,
+				}
 			},
-			TokenKind::True => {
-				self.consume(TokenKind::True);
+			TokenKind::Super => {
+				self.consume(TokenKind::Super)?;
+				self.consume(TokenKind::Access)?;
+				self.expect_ident()?;
 
-				Expr::from(true)
+				SyntaxKind::Super
 			},
-			TokenKind::False => {
-				self.consume(TokenKind::False);
+			e @ (TokenKind::String | TokenKind::Int(_) | TokenKind::Float(_) | TokenKind::True | TokenKind::False) => {
+				self.consume(e)?;
 
-				Expr::from(false)
+				SyntaxKind::Literal
 			},
 			TokenKind::LSquare => {
-				self.consume(TokenKind::LSquare);
-				let mut out: Vec<Expr> = Vec::new();
+				self.consume(TokenKind::LSquare)?;
 
 				loop {
-					let next = self.parse_expression(0)?;
-					out.push(next.0);
+					self.parse_expression(0)?;
 					
 					match self.peek().unwrap_or(TokenKind::EOF) {
-						TokenKind::Comma => self.consume(TokenKind::Comma),
+						TokenKind::Comma => self.consume(TokenKind::Comma)?,
 						TokenKind::RSquare => break,
-						kind => return Err(SyntaxError::new(kind, self.span())),
+						_ => return Err(self.unexpected(&[TokenKind::Comma, TokenKind::RSquare]).into()),
 					}
 				}
 
-				self.consume(TokenKind::RSquare);
-				Expr::from(out)
+				self.consume(TokenKind::RSquare)?;
+				SyntaxKind::Array
 			},
 			TokenKind::LParen => {
-				self.consume(TokenKind::LParen);
-				let expr = self.parse_expression(0)?;
-				self.consume(TokenKind::RParen);
+				self.consume(TokenKind::LParen)?;
+				self.parse_expression(0)?;
+				self.consume(TokenKind::RParen)?;
 
-				expr.0
+				SyntaxKind::Paren
 			},
-			expr @ TokenKind::Not => {
-				self.consume(TokenKind::Not);
+			op @ (TokenKind::Not | TokenKind::Sub) => {
+				self.consume(op)?;
 				let expr_bp: u8;
 
-				if let Some((_, bp)) = expr.prefix_binding_power() {
+				if let Some((_, bp)) = op.prefix_binding_power() {
 					expr_bp = bp;
 				} else {
 					expr_bp = 0;
 				}
 
-				let expr = self.parse_expression(expr_bp)?;
-
-				Expr::PrefixOp { 
-					op: TokenKind::Not, 
-					expr: Box::new(expr.0),
+				// the smallest int can only be written negated, since the int it's the negative of is too large
+				if op == TokenKind::Sub && self.peek() == Some(TokenKind::Error) && is_min_int_magnitude(self.peek_slice()) {
+					let literal = self.cst.checkpoint();
+					self.consume(TokenKind::Error)?;
+					self.cst.wrap(literal, SyntaxKind::Literal);
+				} else {
+					self.parse_expression(expr_bp)?;
 				}
+
+				SyntaxKind::Prefix
 			},
 			TokenKind::If => {
-				let mut requires_rcurly = true;
+				self.consume(TokenKind::If)?;
+				self.parse_expression(0)?;
+				self.parse_braced_block()?;
 
-				self.consume(TokenKind::If);
-				let condition = Box::new(self.parse_expression(0)?.0);
-				self.consume(TokenKind::LCurly);
-				let then_block = Box::new(self.parse_expression(0)?.0);
+				// since all variables need a value, all ifs must have an else
+				self.consume(TokenKind::Else)?;
 
 				match self.peek().unwrap_or(TokenKind::EOF) {
-					TokenKind::RCurly => {},
-					e @ TokenKind::End => self.consume(e),
-					kind => return Err(SyntaxError::new(kind, self.span()))
+					TokenKind::LCurly => self.parse_braced_block()?,
+					TokenKind::If => self.parse_expression(0)?,
+					_ => return Err(self.unexpected(&[TokenKind::LCurly, TokenKind::If]).into()),
 				};
 
-				self.consume(TokenKind::RCurly);
+				SyntaxKind::Conditional
+			},
+			TokenKind::LCurly => {
+				self.consume(TokenKind::LCurly)?;
+				self.parse_block()?;
 
-				// since all variables need a value, all ifs must have an else
-				self.consume(TokenKind::Else);
+				SyntaxKind::Block
+			},
+			TokenKind::Error => return Err(self.lex_error().into()),
+			_ => return Err(self.unexpected(&EXPRESSION_START).into()),
+		};
 
-				match self.peek().unwrap_or(TokenKind::EOF) {
-					e @ TokenKind::LCurly => self.consume(e),
-					TokenKind::If => requires_rcurly = false,
-					kind => return Err(SyntaxError::new(kind, self.span())),
+		self.cst.wrap(start, kind);
+
+		loop {
+			let peek = self.peek().unwrap_or(TokenKind::EOF);
+
+			if let Some((left_binding_power, ())) = peek.postfix_binding_power() {
+				if left_binding_power < binding_power {
+					break;
 				}
 
-				let else_block = Box::new(self.parse_expression(0)?.0);
+				if peek == TokenKind::LSquare && self.newline_before_peek() {
+					return Err(self.ambiguous().into());
+				}
 
-				match self.peek().unwrap_or(TokenKind::EOF) {
-					TokenKind::RCurly => {},
-					e @ TokenKind::End => self.consume(e),
-					TokenKind::Ident => {
-						if requires_rcurly {
-							return Err(SyntaxError::new(TokenKind::Ident, self.span()))
-						}
-					}
-					kind => return Err(SyntaxError::new(kind, self.span()))
+				self.consume(peek)?;
+
+				let kind = match peek {
+					TokenKind::LSquare => {
+						self.parse_expression(0)?;
+						self.consume(TokenKind::RSquare)?;
+
+						SyntaxKind::Index
+					},
+					_ => {
+						self.expect_ident()?;
+
+						SyntaxKind::Access
+					},
 				};
 
-				if requires_rcurly {
-					self.consume(TokenKind::RCurly);
+				self.cst.wrap(start, kind);
+
+				continue;
+			}
+
+			// anything but an operator ends the expression, and whatever comes after it decides if it can end here
+			if let Some((left_binding_power, right_binding_power)) = peek.infix_binding_power() {
+				if left_binding_power < binding_power {
+					break;
 				}
+				
+				self.consume(peek)?;
+				self.parse_expression(right_binding_power)?;
+				self.cst.wrap(start, SyntaxKind::Infix);
+
+				continue;
+			}
 
-				next_requires_end = false;
-				this_requires_end = false;
+			break;
+		}
+
+		Ok(())
+	}
+
+	/// parses an expression, and derives its `Expr` from the node it made
+	pub(crate) fn parse_value(&mut self) -> Result<Expr, Error> {
+		self.parse_expression(0)?;
+
+		self.lower(self.cst.last().expect("an expression makes a node"))
+	}
+
+	/// an error for a `(` or `[` at the start of a line, which would otherwise continue the expression on the line before
+	fn ambiguous(&self) -> SyntaxError {
+		self.unexpected(&[TokenKind::End])
+	}
+
+	/// consumes the end of a statement, which is a `;`, or a line break before the next token
+	fn parse_end(&mut self) -> Result<(), Error> {
+		match self.peek() {
+			Some(e @ TokenKind::End) => self.consume(e)?,
+			None => {},
+			_ if self.newline_before_peek() => {},
+			_ => return Err(self.unexpected(&[TokenKind::End]).into()),
+		}
+
+		Ok(())
+	}
 
-				Expr::Conditional { condition, then_block, else_block }
+	/// where the identifier that was just consumed is
+	fn ident_location(&self) -> Location {
+		self.location(self.current_span().start)
+	}
+
+	/// parses the arguments of `fn_name(a, b)`
+	fn parse_call(&mut self) -> Result<(), Error> {
+		self.consume(TokenKind::LParen)?;
+
+		if self.peek() == Some(TokenKind::RParen) {
+			self.consume(TokenKind::RParen)?;
+			return Ok(());
+		}
+
+		loop {
+			self.parse_expression(0)?;
+
+			match self.peek().unwrap_or(TokenKind::EOF) {
+				e @ TokenKind::Comma => self.consume(e)?,
+				e @ TokenKind::RParen => {
+					self.consume(e)?;
+					break;
+				},
+				_ => return Err(self.unexpected(&[TokenKind::Comma, TokenKind::RParen]).into()),
+			}
+		}
+
+		Ok(())
+	}
+
+	/// parses a block, `{` included, into its own node
+	fn parse_braced_block(&mut self) -> Result<(), Error> {
+		let start = self.cst.checkpoint();
+
+		self.consume(TokenKind::LCurly)?;
+		self.parse_block()?;
+		self.cst.wrap(start, SyntaxKind::Block);
+
+		Ok(())
+	}
+
+	/// parses the rest of a block after its `{`, up to and including the closing `}`
+	fn parse_block(&mut self) -> Result<(), Error> {
+		self.locals.push(Vec::new());
+
+		while let (Some(TokenKind::Ident), Some(TokenKind::Walrus)) = (self.peek(), self.peek_second()) {
+			let start = self.cst.checkpoint();
+			self.consume(TokenKind::Ident)?;
+			let name = self.ident().to_owned();
+
+			// locals can't be redefined or shadowed by a nested block
+			if self.locals.iter().any(|scope| scope.contains(&name)) {
+				return Err(NameConflictError::new(name, "local").into());
+			}
+
+			self.consume(TokenKind::Walrus)?;
+			self.parse_expression(0)?;
+			self.parse_end()?;
+			self.cst.wrap(start, SyntaxKind::Local);
+
+			// only declared after its value, so a local can't refer to itself
+			self.locals.last_mut().unwrap().push(name);
+		}
+
+		self.parse_expression(0)?;
+
+		match self.peek().unwrap_or(TokenKind::EOF) {
+			TokenKind::RCurly => {},
+			e @ TokenKind::End => self.consume(e)?,
+			_ if self.newline_before_peek() => {},
+			_ => return Err(self.unexpected(&[TokenKind::End, TokenKind::RCurly]).into()),
+		};
+
+		self.consume(TokenKind::RCurly)?;
+		self.locals.pop();
+
+		Ok(())
+	}
+
+	/// parses `= value` after the name of a binding
+	fn parse_assign(&mut self, ident: &'a str, output: &mut Store) -> Result<(), Error> {
+		let location = self.ident_location();
+		self.consume(TokenKind::Assign)?;
+
+		let value = self.parse_value()?;
+
+		output.insert(ident.to_owned(), value, Some(location))?;
+		self.document(output, ident);
+
+		Ok(())
+	}
+
+	/// parses a single `name [as binding] [: type] [in range] [where condition] [= default]`
+	fn parse_import(&mut self, output: &mut Store) -> Result<(), Error> {
+		let node = self.cst.checkpoint();
+		let name = self.expect_ident()?;
+		let start = self.current_span().start;
+
+		let binding = match self.peek() {
+			Some(e @ TokenKind::As) => {
+				self.consume(e)?;
+
+				self.expect_ident()?
 			},
-			kind => return Err(SyntaxError::new(kind, self.span())),
+			_ => name,
 		};
 
-		if let Some(TokenKind::LSquare) = self.peek() {
-			self.consume(TokenKind::LSquare);
-			let index = self.parse_expression(0)?;
+		// covers `name as binding`, so it points at both the input and the binding
+		let location = self.location(start);
 
-			lhs = Expr::ArrayAccess { lhs: Box::new(lhs), index: Box::new(index.0) };
-			
-			self.consume(TokenKind::RSquare);
-		}
+		let static_type = match self.peek() {
+			Some(e @ TokenKind::Colon) => {
+				self.consume(e)?;
+				self.parse_input_type()?
+			},
+			_ => InputType::Any,
+		};
+
+		let mut constraints: Vec<Constraint> = Vec::new();
 
 		loop {
-			let peek = self.peek().unwrap_or(TokenKind::EOF);
+			let kind = match self.peek() {
+				Some(TokenKind::In) => ConstraintKind::In,
+				Some(TokenKind::Where) => ConstraintKind::Where,
+				_ => break,
+			};
+
+			let node = self.cst.checkpoint();
+			self.next();
+			let start = self.span().start;
+			let expr = self.parse_value()?;
+			let source = self.source_since(start).to_owned();
+			self.cst.wrap(node, SyntaxKind::Constraint);
+
+			constraints.push(Constraint { kind, expr, source });
+		}
+
+		let default = match self.peek() {
+			Some(e @ TokenKind::Assign) => {
+				self.consume(e)?;
+				Some(self.parse_value()?)
+			},
+			_ => None,
+		};
+
+		self.cst.wrap(node, SyntaxKind::Input);
+
+		let input = Input { static_type, constraints, default, current: None };
+
+		output.insert_input(name, binding, input, Some(location))?;
+		self.document(output, binding);
 
-			let op = match peek {
-				op @ TokenKind::Equals
-				| op @ TokenKind::Lt
-				| op @ TokenKind::Gt
-				| op @ TokenKind::Lte
-				| op @ TokenKind::Gte
-				| op @ TokenKind::And
-				| op @ TokenKind::Or
-				| op @ TokenKind::Not
-				| op @ TokenKind::Add
-				| op @ TokenKind::Sub
-				| op @ TokenKind::Mul
-				| op @ TokenKind::Div
-				| op @ TokenKind::Mod
-				| op @ TokenKind::Exp
-				| op @ TokenKind::Range
-				| op @ TokenKind::IRange => op,
-				TokenKind::EOF
-				| TokenKind::LCurly
-				| TokenKind::RCurly
-				| TokenKind::RSquare
-				| TokenKind::RParen
-				| TokenKind::Comma
-				| TokenKind::End
-				| TokenKind::As => break,
-				kind => {
-					if !this_requires_end {
+		Ok(())
+	}
+
+	/// parses a type annotation, like `int`, `[string]`, `{ host: string, port: int? }` or `"a" | "b"`
+	fn parse_input_type(&mut self) -> Result<InputType, Error> {
+		let node = self.cst.checkpoint();
+		let start = [TokenKind::Ident, TokenKind::LSquare, TokenKind::LCurly, TokenKind::String];
+
+		let static_type = match self.peek().unwrap_or(TokenKind::EOF) {
+			TokenKind::Ident => match InputType::from_name(self.peek_slice()) {
+				Some(e) => {
+					self.consume(TokenKind::Ident)?;
+					e
+				},
+				None => return Err(self.unexpected(&start).into()),
+			},
+			e @ TokenKind::LSquare => {
+				self.consume(e)?;
+				let inner = self.parse_input_type()?;
+				self.consume(TokenKind::RSquare)?;
+
+				InputType::Array(Box::new(inner))
+			},
+			e @ TokenKind::LCurly => {
+				self.consume(e)?;
+				let mut fields: Vec<(String, InputType)> = Vec::new();
+
+				loop {
+					if fields.is_empty() && self.peek() == Some(TokenKind::RCurly) {
+						self.consume(TokenKind::RCurly)?;
 						break;
 					}
 
-					return Err(SyntaxError::new(kind, self.span()))
-				}
-			};
+					let name = self.expect_ident()?.to_owned();
 
-			if let Some((left_binding_power, right_binding_power)) = op.infix_binding_power() {
-				if left_binding_power < binding_power {
-					break;
+					if fields.iter().any(|(field, _)| *field == name) {
+						return Err(NameConflictError::new(name, "record").into());
+					}
+
+					self.consume(TokenKind::Colon)?;
+					fields.push((name, self.parse_input_type()?));
+
+					match self.peek().unwrap_or(TokenKind::EOF) {
+						e @ TokenKind::Comma => self.consume(e)?,
+						e @ TokenKind::RCurly => {
+							self.consume(e)?;
+							break;
+						},
+						_ => return Err(self.unexpected(&[TokenKind::Comma, TokenKind::RCurly]).into()),
+					}
 				}
-				
-				self.consume(&op);
-				let rhs = self.parse_expression(right_binding_power)?;
-				lhs = Expr::InfixOp { op: op.clone(), lhs: Box::new(lhs), rhs: Box::new(rhs.0) };
 
-				if let Some(TokenKind::RCurly) = self.peek() {
-					return Ok((lhs, false))
+				InputType::Record { fields }
+			},
+			e @ TokenKind::String => {
+				self.consume(e)?;
+				let mut variants: Vec<String> = Vec::new();
+
+				loop {
+					variants.push(self.string()?);
+
+					if self.peek() != Some(TokenKind::Pipe) {
+						break;
+					}
+
+					self.consume(TokenKind::Pipe)?;
+					self.consume(TokenKind::String)?;
 				}
 
-				continue;
-			}
+				InputType::Enum(variants)
+			},
+			_ => return Err(self.unexpected(&start).into()),
+		};
 
-			break;
-		}
+		let static_type = match self.peek() {
+			Some(e @ TokenKind::Question) => {
+				self.consume(e)?;
+				InputType::Optional(Box::new(static_type))
+			},
+			_ => static_type,
+		};
 
-		Ok((lhs, next_requires_end))
+		self.cst.wrap(node, SyntaxKind::Type);
+
+		Ok(static_type)
+	}
+
+	/// `import a, b as c: int = 5;`
+	fn parse_imports(&mut self, output: &mut Store) -> Result<(), Error> {
+		loop {
+			self.parse_import(output)?;
+
+			match self.peek() {
+				Some(e @ TokenKind::Comma) => self.consume(e)?,
+				_ => return Ok(()),
+			}
+		}
 	}
 
-	/// returns whether the next token should be TokenKind::End
-	/// for cases like scopes and conditionals
-	fn parse_assign(&mut self, ident: &'a str, output: &mut Store) -> Result<bool, SyntaxError> {
-		self.consume(TokenKind::Assign);
+	/// `import "path" as name;`
+	fn parse_module_import(&mut self, output: &mut Store) -> Result<(), Error> {
+		self.consume(TokenKind::String)?;
+		let path = self.string()?;
 
-		let value = self.parse_expression(0)?;
+		self.consume(TokenKind::As)?;
+		let name = self.expect_ident()?;
+		let location = self.ident_location();
 
-		output.insert(ident.to_owned(), value.0);
+		// the whole statement is read before the module is, so it's all skipped if the module can't be loaded
+		let id = self.load_module(&path, output)?;
+		output.insert(name, Expr::Module(id), Some(location))?;
+		self.document(output, name);
 
-		Ok(value.1)
+		Ok(())
 	}
 
-	fn parse_import(&mut self, )
+	/// `from "path" import a, b as c;`
+	fn parse_from_import(&mut self, output: &mut Store) -> Result<(), Error> {
+		self.consume(TokenKind::String)?;
+		let path = self.string()?;
 
-	fn parse_imports(&mut self, output: &mut Store) {
-		self.consume(TokenKind::Import);
+		self.consume(TokenKind::Import)?;
+		let mut imports: Vec<(&str, &str, Location)> = Vec::new();
 
-		match self.peek().unwrap_or(TokenKind::EOF) {
-			TokenKind::Ident => {
-				self.consume(TokenKind::Ident);
-				let ident = self.slice();
-				let input = Input { static_type: InputType::Any, default: None, current: None };
+		loop {
+			let name = self.expect_ident()?;
+
+			let binding = match self.peek() {
+				Some(e @ TokenKind::As) => {
+					self.consume(e)?;
+
+					self.expect_ident()?
+				},
+				_ => name,
+			};
 
-				output.insert_input(ident, input);
+			imports.push((name, binding, self.ident_location()));
+
+			match self.peek() {
+				Some(e @ TokenKind::Comma) => self.consume(e)?,
+				_ => break,
+			}
+		}
+
+		let id = self.load_module(&path, output)?;
+
+		for (name, binding, location) in imports {
+			if !output.modules[&id].outputs.iter().any(|e| e.name == name) {
+				return Err(UndefinedError::new(name, path.as_str()).into());
 			}
+
+			let value = Expr::Access { lhs: Box::new(Expr::Module(id.clone())), field: name.to_owned() };
+
+			output.insert(binding, Expr::Spanned { location: location.clone(), expr: Box::new(value) }, Some(location))?;
+			self.document(output, binding);
 		}
+
+		Ok(())
 	}
 
-	pub(crate) fn parse_input<T: AlmondError>(&mut self, output: &mut Store) -> Result<&mut Store, T> {
+	/// parses the file at `path` into `output.modules`, returning its id
+	fn load_module(&mut self, path: &str, output: &mut Store) -> Result<String, Error> {
+
+		let resolver = match self.resolver {
+			Some(e) => e,
+			None => return Err(ResolveError::new(path, "no module resolver was given").into()),
+		};
+
+		let id = resolver.resolve(path, self.file.as_deref())?;
+
+		if self.loading.contains(&id) {
+			let mut cycle = self.loading.clone();
+			cycle.push(id);
+
+			return Err(ResolveError::new(path, format!("circular import `{}`", cycle.join("` -> `"))).into());
+		}
+
+		if output.modules.contains_key(&id) {
+			return Ok(id);
+		}
+
+		let source = resolver.load(&id)?;
+		let mut module = Store::new();
+
+		Parser::module(&source, resolver, id.clone(), self.loading.clone()).parse_input(&mut module)?;
+		output.modules.insert(id.clone(), module);
+
+		Ok(id)
+	}
+
+	/// `export a, b as c;` or `export a = 5;`
+	fn parse_exports(&mut self, output: &mut Store) -> Result<(), Error> {
 		loop {
-			let next = self.next();
+			let binding = self.expect_ident()?;
+			let start = self.current_span().start;
+
+			if self.peek() == Some(TokenKind::Assign) {
+				let location = self.ident_location();
+				self.parse_assign(binding, output)?;
+				output.insert_output(binding, binding, Some(location))?;
+
+				return Ok(());
+			}
 
-			let end_required = match next.unwrap_or(TokenKind::EOF) {
-				TokenKind::Ident => self.parse_assign(self.slice(), output)?,
-				TokenKind::Import => {
-					self.parse_import(output);
-					true
+			let name = match self.peek() {
+				Some(e @ TokenKind::As) => {
+					self.consume(e)?;
+
+					self.expect_ident()?
 				},
-				TokenKind::Comment => continue,
-				TokenKind::EOF => return Ok(output),
-				kind => return Err(SyntaxError::new(kind, self.span())),
+				_ => binding,
 			};
 
-			if end_required {
-				self.consume(TokenKind::End);
+			output.insert_output(name, binding, Some(self.location(start)))?;
+
+			match self.peek() {
+				Some(e @ TokenKind::Comma) => self.consume(e)?,
+				_ => return Ok(()),
+			}
+		}
+	}
+
+	/// parses one statement, returning whether there's anything left to parse
+	fn parse_statement(&mut self, output: &mut Store) -> Result<bool, Error> {
+		let start = self.cst.checkpoint();
+		let next = self.peek().unwrap_or(TokenKind::EOF);
+		self.take_docs();
+
+		if next != TokenKind::EOF {
+			self.consume(next)?;
+		}
+
+		let kind = match next {
+			TokenKind::Ident => {
+				self.parse_assign(self.ident(), output)?;
+				SyntaxKind::Binding
+			},
+			TokenKind::Import if self.peek() == Some(TokenKind::String) => {
+				self.parse_module_import(output)?;
+				SyntaxKind::ModuleImport
+			},
+			TokenKind::Import => {
+				self.parse_imports(output)?;
+				SyntaxKind::Import
+			},
+			TokenKind::From => {
+				self.parse_from_import(output)?;
+				SyntaxKind::FromImport
+			},
+			TokenKind::Export => {
+				self.parse_exports(output)?;
+				SyntaxKind::Export
+			},
+			// empty statements are harmless, and come up after a statement that ends in `}`
+			TokenKind::End => return Ok(true),
+			TokenKind::EOF => return Ok(false),
+			TokenKind::Error => {
+				let slice = self.slice();
+				return Err(LexError::new(slice, self.current_span(), describe_error(slice)).with_file(self.file.clone()).into());
+			},
+			kind => {
+				let expected = vec![TokenKind::Ident, TokenKind::Import, TokenKind::From, TokenKind::Export, TokenKind::EOF];
+				return Err(SyntaxError::new(kind, self.current_span(), expected).with_file(self.file.clone()).into());
+			},
+		};
+
+		self.parse_end()?;
+		self.cst.wrap(start, kind);
+
+		Ok(true)
+	}
+
+	/// exports can come before the bindings they refer to, so they're checked once everything is parsed
+	fn undefined_exports(&self, output: &Store) -> Vec<Error> {
+		output.outputs.iter()
+			.filter(|e| !output.contents.contains_key(&e.binding))
+			.map(|e| UndefinedError::new(e.binding.to_owned(), "global").with_location(output.output_locations.get(&e.name).cloned()).into())
+			.collect()
+	}
+
+	/// skips to the start of the next statement after an error
+	/// that's just after a `;` or `}`, or at a keyword or `name =` that starts a statement,
+	/// outside of the blocks the error was in, so it doesn't pick back up in the middle of one
+	fn synchronize(&mut self) {
+		let mut depth = self.locals.len();
+		self.locals.clear();
+
+		loop {
+			match self.peek().unwrap_or(TokenKind::EOF) {
+				TokenKind::EOF => return,
+				TokenKind::Import
+				| TokenKind::From
+				| TokenKind::Export if depth == 0 => return,
+				TokenKind::Ident if depth == 0 && self.peek_second() == Some(TokenKind::Assign) => return,
+				TokenKind::LCurly if depth > 0 => depth += 1,
+				TokenKind::RCurly if depth > 0 => depth -= 1,
+				TokenKind::End
+				| TokenKind::RCurly if depth == 0 => {
+					self.next();
+					return;
+				},
+				_ => {},
+			}
+
+			self.next();
+		}
+	}
+
+	pub(crate) fn parse_input<'s>(&mut self, output: &'s mut Store) -> Result<&'s mut Store, Error> {
+		while self.parse_statement(output)? {}
+
+		match self.undefined_exports(output).into_iter().next() {
+			Some(e) => Err(e),
+			None => Ok(output),
+		}
+	}
+
+	/// like `parse_input`, but keeps going after an error, returning every error it finds
+	pub(crate) fn parse_input_recovering(&mut self, output: &mut Store) -> Vec<Error> {
+		let mut errors: Vec<Error> = Vec::new();
+
+		loop {
+			let start = self.cst.checkpoint();
+
+			match self.parse_statement(output) {
+				Ok(true) => {},
+				Ok(false) => break,
+				Err(e) => {
+					errors.push(e);
+					self.synchronize();
+					self.cst.wrap(start, SyntaxKind::Error);
+				},
 			}
 		}
+
+		errors.extend(self.undefined_exports(output));
+
+		errors
 	}
 }
 
-trait Operator {
+/// every token that can start an expression
+const EXPRESSION_START: [TokenKind; 13] = [
+	TokenKind::Ident,
+	TokenKind::Super,
+	TokenKind::String,
+	TokenKind::Int(0),
+	TokenKind::Float(0.0),
+	TokenKind::True,
+	TokenKind::False,
+	TokenKind::LSquare,
+	TokenKind::LParen,
+	TokenKind::Not,
+	TokenKind::Sub,
+	TokenKind::If,
+	TokenKind::LCurly,
+];
+
+pub(crate) trait Operator {
 	fn prefix_binding_power(&self) -> Option<((), u8)>;
 	fn infix_binding_power(&self) -> Option<(u8, u8)>;
 	fn postfix_binding_power(&self) -> Option<(u8, ())>;
@@ -260,7 +699,9 @@ trait Operator {
 impl Operator for TokenKind {
 	fn prefix_binding_power(&self) -> Option<((), u8)> {
 		let result = match self {
-			TokenKind::Not => ((), 51),
+			// binds tighter than any infix operator, so `-2 ** 2` is `(-2) ** 2` and `-1..5` is `(-1)..5`
+			TokenKind::Not
+			| TokenKind::Sub => ((), 51),
 			_ => return None,
 		};
 
@@ -293,7 +734,8 @@ impl Operator for TokenKind {
 
 	fn postfix_binding_power(&self) -> Option<(u8, ())> {
 		let result = match self {
-			TokenKind::LSquare => (53, ()),
+			TokenKind::LSquare
+			| TokenKind::Access => (53, ()),
 			_ => return None,
 		};
 
@@ -303,11 +745,11 @@ impl Operator for TokenKind {
 
 #[cfg(test)]
 mod tests {
-    use crate::{parser::{eval, ast::{Expr, Literal}}, lexer::tokens::TokenKind};
+    use crate::{parser::{eval, eval_all, ast::{Expr, Literal, Input, InputType, Output}, errors::Error}, lexer::tokens::TokenKind};
 
 	#[test]
 	fn multiple() {
-		let store = eval("nice = 23; cool = 7;");
+		let store = eval("nice = 23; cool = 7;").unwrap();
 
 
 		assert_eq!(
@@ -333,7 +775,7 @@ mod tests {
 			float = 324.2356;
 			boolF = false;
 			boolT = true;
-		"#);
+		"#).unwrap();
 
 
 		assert_eq!(
@@ -374,7 +816,7 @@ mod tests {
 
 	#[test]
 	fn infix() {
-		let store = eval("nice = 23 + 7;");
+		let store = eval("nice = 23 + 7;").unwrap();
 
 		assert_eq!(
 			store.get_ast("nice"),
@@ -392,9 +834,94 @@ mod tests {
 		)
 	}
 
+	#[test]
+	fn infix_not_equals() {
+		let store = eval("a = 3 != 4; b = 3 != 3; c = [1] != [1] || 2 == 2;").unwrap();
+
+		assert_eq!(store.get("a", None), Literal::Bool(true));
+		assert_eq!(store.get("b", None), Literal::Bool(false));
+		assert_eq!(store.get("c", None), Literal::Bool(true));
+	}
+
+	#[test]
+	fn negation() {
+		let store = eval("nice = 5-3; cool = 5 - -3; epic = -(a+b);").unwrap();
+
+		assert_eq!(
+			store.get_ast("nice"),
+			Some(
+				&Expr::InfixOp {
+					op: TokenKind::Sub,
+					lhs: Box::new(Expr::from(5)),
+					rhs: Box::new(Expr::from(3)),
+				}
+			)
+		);
+
+		assert_eq!(
+			store.get_ast("cool"),
+			Some(
+				&Expr::InfixOp {
+					op: TokenKind::Sub,
+					lhs: Box::new(Expr::from(5)),
+					rhs: Box::new(
+						Expr::PrefixOp { op: TokenKind::Sub, expr: Box::new(Expr::from(3)) }
+					),
+				}
+			)
+		);
+
+		assert_eq!(
+			store.get_ast("epic"),
+			Some(
+				&Expr::PrefixOp {
+					op: TokenKind::Sub,
+					expr: Box::new(
+						Expr::InfixOp {
+							op: TokenKind::Add,
+							lhs: Box::new(Expr::Ref("a".to_owned())),
+							rhs: Box::new(Expr::Ref("b".to_owned())),
+						}
+					),
+				}
+			)
+		);
+	}
+
+	#[test]
+	fn interpolation() {
+		let store = eval(r#"url = "https://{host}:{port + 1}/api"; plain = "\{not} {{ a := 1; a }}";"#).unwrap();
+
+		assert_eq!(
+			store.get_ast("url"),
+			Some(
+				&Expr::Interpolation(vec![
+					Expr::from("https://".to_owned()),
+					Expr::Ref("host".to_owned()),
+					Expr::from(":".to_owned()),
+					Expr::InfixOp {
+						op: TokenKind::Add,
+						lhs: Box::new(Expr::Ref("port".to_owned())),
+						rhs: Box::new(Expr::from(1)),
+					},
+					Expr::from("/api".to_owned()),
+				])
+			)
+		);
+
+		let mut references = store.get_ast("url").unwrap().references();
+		references.sort();
+		assert_eq!(references, vec!["host", "port"]);
+
+		assert!(store.get_ast("plain").unwrap().references().is_empty());
+
+		let err = eval(r#"url = "{host"; port = 5;"#).unwrap_err();
+		assert_eq!(err.code(), "A0009");
+	}
+
 	#[test]
 	fn infix_chained() {
-		let store = eval("nice = 23 + 7 * 3;");
+		let store = eval("nice = 23 + 7 * 3;").unwrap();
 
 		assert_eq!(
 			store.get_ast("nice"),
@@ -422,7 +949,7 @@ mod tests {
 
 	#[test]
 	fn infix_grouped() {
-		let store = eval("nice = (23 + 7) * 3;");
+		let store = eval("nice = (23 + 7) * 3;").unwrap();
 
 		assert_eq!(
 			store.get_ast("nice"),
@@ -450,7 +977,7 @@ mod tests {
 
 	#[test]
 	fn array() {
-		let store = eval(r#"nice = ["cool", 1, 3.245, true, false];"#);
+		let store = eval(r#"nice = ["cool", 1, 3.245, true, false];"#).unwrap();
 
 		assert_eq!(
 			store.get_ast("nice"),
@@ -472,7 +999,7 @@ mod tests {
 
 	#[test]
 	fn array_nested() {
-		let store = eval("nice = [1, 2, [3, 4]];");
+		let store = eval("nice = [1, 2, [3, 4]];").unwrap();
 
 		assert_eq!(
 			store.get_ast("nice"),
@@ -499,7 +1026,7 @@ mod tests {
 
 	#[test]
 	fn array_with_expr() {
-		let store = eval("nice = [1 + 4, 6, 5 * 2, 11];");
+		let store = eval("nice = [1 + 4, 6, 5 * 2, 11];").unwrap();
 
 		assert_eq!(
 			store.get_ast("nice"),
@@ -536,7 +1063,7 @@ mod tests {
 
 	#[test]
 	fn array_in_expr() {
-		let store = eval("nice = [1, 2, 3, 4] + [4, 3, 2, 1];");
+		let store = eval("nice = [1, 2, 3, 4] + [4, 3, 2, 1];").unwrap();
 
 		assert_eq!(
 			store.get_ast("nice"),
@@ -574,7 +1101,7 @@ mod tests {
 
 	#[test]
 	fn array_index() {
-		let store = eval("nice = [1, 2, 3, 4]; cool = nice[3];");
+		let store = eval("nice = [1, 2, 3, 4]; cool = nice[3];").unwrap();
 
 		assert_eq!(
 			store.get_ast("cool"),
@@ -593,7 +1120,7 @@ mod tests {
 
 	#[test]
 	fn ranges() {
-		let store = eval("nice = 1..5; cool = 1..=5;");
+		let store = eval("nice = 1..5; cool = 1..=5;").unwrap();
 
 		assert_eq!(
 			store.get_ast("nice"),
@@ -628,7 +1155,7 @@ mod tests {
 
 	#[test]
 	fn ranges_in_expr() {
-		let store = eval("nice = 1..5 * 3;");
+		let store = eval("nice = 1..5 * 3;").unwrap();
 
 		assert_eq!(
 			store.get_ast("nice"),
@@ -665,7 +1192,7 @@ mod tests {
 			}
 
 			epic = cool * 2;
-		"#);
+		"#).unwrap();
 
 		assert_eq!(
 			store.get_ast("cool"),
@@ -704,7 +1231,7 @@ mod tests {
 			} else {
 				nice
 			}
-		"#);
+		"#).unwrap();
 
 		assert_eq!(
 			store.get_ast("cool"),
@@ -763,7 +1290,7 @@ mod tests {
 			} else {
 				nice
 			}
-		"#);
+		"#).unwrap();
 
 		assert_eq!(
 			store.get_ast("cool"),
@@ -808,4 +1335,387 @@ mod tests {
 			)
 		)
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn block() {
+		let store = eval("total = { base := 10; extra := base * 2; base + extra };").unwrap();
+
+		assert_eq!(
+			store.get_ast("total"),
+			Some(
+				&Expr::Block {
+					locals: vec![
+						("base".to_owned(), Expr::from(10)),
+						(
+							"extra".to_owned(),
+							Expr::InfixOp {
+								op: TokenKind::Mul,
+								lhs: Box::new(
+									Expr::Ref("base".to_owned())
+								),
+								rhs: Box::new(
+									Expr::from(2)
+								)
+							}
+						)
+					],
+					body: Box::new(
+						Expr::InfixOp {
+							op: TokenKind::Add,
+							lhs: Box::new(
+								Expr::Ref("base".to_owned())
+							),
+							rhs: Box::new(
+								Expr::Ref("extra".to_owned())
+							)
+						}
+					)
+				}
+			)
+		);
+
+		assert_eq!(store.get_ast("base"), None);
+	}
+
+	#[test]
+	fn block_shadowing() {
+		let err = eval("total = { base := 10; inner := { base := 2; base }; inner };").unwrap_err();
+
+		assert!(err.to_string().contains("`base` already exists in scope `local`"));
+	}
+
+	#[test]
+	fn imports() {
+		let store = eval("import port: int = 8080, host, name as alias: string; url = host;").unwrap();
+
+		assert_eq!(
+			store.inputs.get("port"),
+			Some(
+				&Input {
+					static_type: InputType::Int,
+					constraints: Vec::new(),
+					default: Some(Expr::from(8080)),
+					current: None
+				}
+			)
+		);
+
+		assert_eq!(
+			store.inputs.get("host"),
+			Some(
+				&Input {
+					static_type: InputType::Any,
+					constraints: Vec::new(),
+					default: None,
+					current: None
+				}
+			)
+		);
+
+		assert_eq!(
+			store.get_ast("alias"),
+			Some(
+				&Expr::Import("name".to_owned())
+			)
+		);
+
+		assert_eq!(store.get_ast("name"), None);
+	}
+
+	#[test]
+	fn imports_duplicate() {
+		let err = eval("import port, port as other;").unwrap_err();
+
+		assert!(err.to_string().contains("`port` already exists in scope `inputs`"));
+	}
+
+	#[test]
+	fn name_conflicts() {
+		let conflict = |source: &str| match eval(source).unwrap_err() {
+			Error::NameConflict(e) => {
+				let first = &source[e.first().unwrap().span.clone()];
+				let second = &source[e.second().unwrap().span.clone()];
+
+				(first.to_owned(), second.to_owned())
+			},
+			e => panic!("expected a name conflict, found {}", e),
+		};
+
+		assert_eq!(conflict("nice = 1; cool = 2; nice = 3;"), ("nice".to_owned(), "nice".to_owned()));
+		assert_eq!(conflict("import port as nice; nice = 3;"), ("port as nice".to_owned(), "nice".to_owned()));
+		assert_eq!(conflict("nice = 3; import nice;"), ("nice".to_owned(), "nice".to_owned()));
+		assert_eq!(conflict("import port, port as other;"), ("port".to_owned(), "port as other".to_owned()));
+		assert_eq!(conflict("nice = 1; export nice = 2;"), ("nice".to_owned(), "nice".to_owned()));
+		assert_eq!(conflict("a = 1; b = 2; export a as c, b as c;"), ("a as c".to_owned(), "b as c".to_owned()));
+	}
+
+	#[test]
+	fn import_types() {
+		let store = eval(r#"
+			import hosts: [string], db: { host: string, port: int? }, level: "debug" | "info";
+		"#).unwrap();
+
+		assert_eq!(
+			store.inputs.get("hosts").map(|e| &e.static_type),
+			Some(
+				&InputType::Array(
+					Box::new(InputType::String)
+				)
+			)
+		);
+
+		assert_eq!(
+			store.inputs.get("db").map(|e| &e.static_type),
+			Some(
+				&InputType::Record {
+					fields: vec![
+						("host".to_owned(), InputType::String),
+						(
+							"port".to_owned(),
+							InputType::Optional(
+								Box::new(InputType::Int)
+							)
+						)
+					]
+				}
+			)
+		);
+
+		assert_eq!(
+			store.inputs.get("level").map(|e| &e.static_type),
+			Some(
+				&InputType::Enum(
+					vec!["debug".to_owned(), "info".to_owned()]
+				)
+			)
+		);
+	}
+
+	#[test]
+	fn exports() {
+		let store = eval("export nice, cool as epic; export total = nice + 1; nice = 2; cool = 3;").unwrap();
+
+		assert_eq!(
+			store.outputs,
+			vec![
+				Output { name: "nice".to_owned(), binding: "nice".to_owned() },
+				Output { name: "epic".to_owned(), binding: "cool".to_owned() },
+				Output { name: "total".to_owned(), binding: "total".to_owned() },
+			]
+		);
+
+		assert_eq!(
+			store.get_ast("total"),
+			Some(
+				&Expr::InfixOp {
+					op: TokenKind::Add,
+					lhs: Box::new(
+						Expr::Ref("nice".to_owned())
+					),
+					rhs: Box::new(
+						Expr::from(1)
+					)
+				}
+			)
+		);
+	}
+
+	#[test]
+	fn exports_undefined() {
+		let err = eval("export missing;").unwrap_err();
+
+		assert!(err.to_string().contains("`missing` does not exist in scope `global`"));
+	}
+
+	#[test]
+	fn syntax_errors() {
+		let err = eval("nice = (1 + 2;").unwrap_err();
+		assert_eq!(err.to_string(), "SyntaxError: Unexpected token `;` at char `13`, expected `)`");
+
+		let err = eval("nice = { a := 1; a").unwrap_err();
+		assert_eq!(err.to_string(), "SyntaxError: Unexpected token `end of file` at char `18`, expected `;` or `}`");
+
+		let err = eval("nice = [1, 2").unwrap_err();
+		assert_eq!(err.to_string(), "SyntaxError: Unexpected token `end of file` at char `12`, expected `,` or `]`");
+
+		let err = eval("nice = 5 cool = 4;").unwrap_err();
+		assert_eq!(err.to_string(), "SyntaxError: Unexpected token `identifier` at char `9`, expected `;`");
+
+		let err = eval("import port: number;").unwrap_err();
+		assert_eq!(err.to_string(), "SyntaxError: Unexpected token `identifier` at char `13`, expected `identifier`, `[`, `{` or `string`");
+	}
+
+	#[test]
+	fn comments() {
+		let store = eval(r#"
+			// a line comment
+			nice = 1 + /* inline */ 2 // trailing
+				* 3;
+
+			/* block comments /* can nest */
+			   over lines */
+
+			/// the port to listen on
+			///   with more detail
+			import port: int = 80;
+
+			/// not documentation once a statement
+			cool = 5;
+			epic = [1, /// comment
+				2];
+			last = 5;
+			x = { /// inner
+				a := 1; a }
+			y = 2
+			z = 1 /// trailing
+			w = 2
+		"#).unwrap();
+
+		assert_eq!(store.get("nice", None), Literal::Int(7));
+		assert_eq!(store.docs("port"), Some("the port to listen on\n  with more detail"));
+		assert_eq!(store.docs("cool"), Some("not documentation once a statement"));
+		assert_eq!(store.docs("epic"), None);
+		assert_eq!(store.docs("nice"), None);
+		assert_eq!(store.docs("last"), None);
+		assert_eq!(store.docs("y"), None);
+		assert_eq!(store.docs("w"), None);
+
+		let err = eval("nice = 1; /* never /* closed */").unwrap_err();
+		assert!(err.to_string().ends_with("the block comment is never closed"), "{}", err);
+	}
+
+	#[test]
+	fn optional_semicolons() {
+		let store = eval(r#"
+			import port: int = 80
+			host = "localhost"
+			total = port
+				+ 1
+			items = [
+				1,
+				2
+			]
+			first = items
+				.len
+			value = {
+				a := 2
+				b := a * 3
+				a + b
+			}
+			cool = if total > 5 { 1 } else { 2 }
+			export total, value as epic
+			last = 3; final = 4
+		"#).unwrap();
+
+		assert_eq!(store.get("total", None), Literal::Int(81));
+		assert_eq!(store.get("value", None), Literal::Int(8));
+		assert_eq!(store.get("cool", None), Literal::Int(1));
+		assert_eq!(store.get("final", None), Literal::Int(4));
+
+		let err = eval("nice = 1 cool = 2").unwrap_err();
+		assert_eq!(err.to_string(), "SyntaxError: Unexpected token `identifier` at char `9`, expected `;`");
+
+		// a line starting with `(` or `[` could be a call, an index, or a mistake, so it needs a `;` before it
+		let err = eval("nice = items\n[0]").unwrap_err();
+		assert_eq!(err.to_string(), "SyntaxError: Unexpected token `[` at char `13`, expected `;`");
+
+		let err = eval("nice = len\n(items)").unwrap_err();
+		assert_eq!(err.to_string(), "SyntaxError: Unexpected token `(` at char `11`, expected `;`");
+	}
+
+	#[test]
+	fn identifiers() {
+		let store = eval(r#"
+			_private = 1
+			größe = 2
+			名前 = "name"
+			`content-type` = "application/json"
+			import `x-api-key` as key: string = "secret"
+			total = _private + größe
+			header = `content-type`
+		"#).unwrap();
+
+		assert_eq!(store.get("total", None), Literal::Int(3));
+		assert_eq!(store.get("名前", None), Literal::String("name".to_owned()));
+		assert_eq!(store.get("header", None), Literal::String("application/json".to_owned()));
+		assert!(store.inputs.contains_key("x-api-key"));
+		assert_eq!(store.get("key", None), Literal::String("secret".to_owned()));
+
+		let store = eval("import db: { `max-connections`: int }\nlimit = db.`max-connections`").unwrap();
+		assert_eq!(
+			store.inputs["db"].static_type,
+			InputType::Record { fields: vec![("max-connections".to_owned(), InputType::Int)] }
+		);
+	}
+
+	#[test]
+	fn recovery() {
+		let (store, errors) = eval_all(r#"
+			nice = (1 + 2;
+			cool = 7;
+			broken = { a := 1; a + };
+			export missing;
+			epic = if cool > 5 { 1 } else { 2 }
+			done = [1, 2;
+			last = 3;
+			nested = { x := (; y := { 2 }; x }
+			after = 4
+		"#);
+
+		let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+
+		assert_eq!(
+			errors,
+			vec![
+				"SyntaxError: Unexpected token `;` at char `17`, expected `)`",
+				"SyntaxError: Unexpected token `}` at char `58`, expected `identifier`, `super`, `string`, `int`, `float`, `true`, `false`, `[`, `(`, `!`, `-`, `if` or `{`",
+				"SyntaxError: Unexpected token `;` at char `134`, expected `,` or `]`",
+				"SyntaxError: Unexpected token `;` at char `169`, expected `identifier`, `super`, `string`, `int`, `float`, `true`, `false`, `[`, `(`, `!`, `-`, `if` or `{`",
+				"UndefinedError: Member with name `missing` does not exist in scope `global`",
+			]
+		);
+
+		assert_eq!(store.get_ast("cool"), Some(&Expr::from(7)));
+		assert_eq!(store.get_ast("last"), Some(&Expr::from(3)));
+		assert_eq!(store.get_ast("after"), Some(&Expr::from(4)));
+		assert!(store.get_ast("epic").is_some());
+		assert_eq!(store.get_ast("nice"), None);
+	}
+
+	#[test]
+	fn recovery_without_semicolons() {
+		let (store, errors) = eval_all("nice = (1 + 2\ncool = 7\nepic = [1, 2\nlast = 3\n");
+
+		let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+
+		assert_eq!(
+			errors,
+			vec![
+				"SyntaxError: Unexpected token `identifier` at char `14`, expected `)`",
+				"SyntaxError: Unexpected token `identifier` at char `36`, expected `,` or `]`",
+			]
+		);
+
+		assert_eq!(store.get_ast("cool"), Some(&Expr::from(7)));
+		assert_eq!(store.get_ast("last"), Some(&Expr::from(3)));
+	}
+
+	#[test]
+	fn spans() {
+		let source = "nice = 23 + (7 * cool);\nimport port = 80;";
+		let store = eval(source).unwrap();
+
+		let nice = store.get_ast("nice").unwrap();
+		assert_eq!(nice.location().unwrap().span, 7..22);
+
+		let rhs = match nice.inner() {
+			Expr::InfixOp { rhs, .. } => rhs,
+			e => panic!("expected an infix op, found {:?}", e),
+		};
+
+		assert_eq!(&source[rhs.location().unwrap().span.clone()], "(7 * cool)");
+		assert_eq!(store.locations["nice"].span, 0..4);
+		assert_eq!(&source[store.locations["port"].span.clone()], "port");
+		assert_eq!(nice.location().unwrap().file, None);
+	}
+}
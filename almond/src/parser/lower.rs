@@ -0,0 +1,156 @@
+use std::ops::Range;
+
+use crate::lexer::{Token, tokens::TokenKind};
+
+use super::{Parser, ident_name, strings, ast::{Expr, Location}, cst::{SyntaxKind, SyntaxNode}, errors::{LexError, Error}};
+
+impl<'a> Parser<'a> {
+	/// the `Expr` of an expression node, with where every part of it was written
+	pub(crate) fn lower(&self, node: &SyntaxNode<'a>) -> Result<Expr, Error> {
+		let nodes = node.nodes().collect::<Vec<&SyntaxNode>>();
+		let tokens = node.tokens().collect::<Vec<&Token>>();
+
+		let expr = match node.kind {
+			SyntaxKind::Literal => match tokens[0].kind {
+				TokenKind::Int(e) => Expr::from(e),
+				TokenKind::Float(e) => Expr::from(e),
+				TokenKind::True => Expr::from(true),
+				TokenKind::False => Expr::from(false),
+				_ => self.lower_string(tokens[0])?,
+			},
+			SyntaxKind::Ref => Expr::Ref(ident_name(tokens[0].slice).to_owned()),
+			SyntaxKind::Super => Expr::Super(ident_name(tokens[tokens.len() - 1].slice).to_owned()),
+			SyntaxKind::Paren => self.lower(nodes[0])?,
+			// `-9223372036854775808`, where the int on its own was too large to lex
+			SyntaxKind::Prefix if nodes[0].first_token().map(|e| e.kind) == Some(TokenKind::Error) => Expr::from(i64::MIN),
+			SyntaxKind::Prefix => Expr::PrefixOp { op: tokens[0].kind, expr: Box::new(self.lower(nodes[0])?) },
+			SyntaxKind::Infix => Expr::InfixOp {
+				op: tokens[0].kind,
+				lhs: Box::new(self.lower(nodes[0])?),
+				rhs: Box::new(self.lower(nodes[1])?),
+			},
+			SyntaxKind::Call => Expr::FnCall {
+				fn_name: ident_name(tokens[0].slice).to_owned(),
+				args: nodes.into_iter().map(|e| self.lower(e)).collect::<Result<Vec<Expr>, Error>>()?,
+			},
+			SyntaxKind::Array => Expr::from(nodes.into_iter().map(|e| self.lower(e)).collect::<Result<Vec<Expr>, Error>>()?),
+			SyntaxKind::Index => Expr::ArrayAccess { lhs: Box::new(self.lower(nodes[0])?), index: Box::new(self.lower(nodes[1])?) },
+			SyntaxKind::Access => Expr::Access {
+				lhs: Box::new(self.lower(nodes[0])?),
+				field: ident_name(tokens[tokens.len() - 1].slice).to_owned(),
+			},
+			SyntaxKind::Conditional => Expr::Conditional {
+				condition: Box::new(self.lower(nodes[0])?),
+				then_block: Box::new(self.lower(nodes[1])?),
+				else_block: Box::new(self.lower(nodes[2])?),
+			},
+			SyntaxKind::Block => {
+				let (locals, body) = nodes.split_at(nodes.len() - 1);
+
+				let locals = locals.iter()
+					.map(|e| {
+						let name = ident_name(e.tokens().next().unwrap().slice).to_owned();
+
+						Ok((name, self.lower(e.nodes().next().unwrap())?))
+					})
+					.collect::<Result<Vec<(String, Expr)>, Error>>()?;
+
+				let body = self.lower(body[0])?;
+
+				// a block with no `:=` bindings is just its body
+				match locals.is_empty() {
+					true => body,
+					false => Expr::Block { locals, body: Box::new(body) },
+				}
+			},
+			kind => unreachable!("a `{:?}` node isn't an expression", kind),
+		};
+
+		Ok(self.spanned(expr, node.span().unwrap_or_default()))
+	}
+
+	/// wraps `expr` with where it was written
+	fn spanned(&self, expr: Expr, span: Range<usize>) -> Expr {
+		let location = Location { file: self.file.clone(), span };
+
+		match expr {
+			// a parenthesized expression or a block takes the span of its brackets
+			Expr::Spanned { expr, .. } => Expr::Spanned { location, expr },
+			expr => Expr::Spanned { location, expr: Box::new(expr) },
+		}
+	}
+
+	/// the value of a string token, which is an `Expr::Interpolation` if it has any `{expr}` in it
+	fn lower_string(&self, token: &Token<'a>) -> Result<Expr, Error> {
+		let slice = token.slice;
+		let span = token.span.clone();
+		let lex_error = |e: String| LexError::new(slice, span.clone(), e).with_file(self.file.clone());
+
+		let mut open = match strings::find_interpolation(slice, 0) {
+			Some(e) => e,
+			None => return Ok(Expr::from(strings::unquote(slice).map_err(lex_error)?)),
+		};
+
+		let quotes = strings::quotes(slice);
+		let contents = &slice[quotes..slice.len() - quotes];
+
+		if contents.contains(strings::PLACEHOLDER) {
+			return Err(lex_error("a string with an interpolation can't have a null character in it, write `\\0` instead".to_owned()).into());
+		}
+
+		// the text with a placeholder for each expression, so a multi-line string can be dedented before it's split up
+		let mut template = String::new();
+		let mut exprs: Vec<Expr> = Vec::new();
+		let mut text_start = 0;
+
+		loop {
+			template.push_str(&contents[text_start..open]);
+			template.push(strings::PLACEHOLDER);
+
+			// the expression is parsed in place, so its spans point into the string, and it can't go past the closing quotes
+			let mut parser = self.embedded(span.start + quotes + open + 1, span.end - quotes);
+			let value = parser.parse_value().and_then(|e| {
+				parser.consume(TokenKind::RCurly)?;
+				Ok(e)
+			});
+
+			match value {
+				Ok(e) => exprs.push(e),
+				Err(_) if parser.peek().is_none() => {
+					return Err(lex_error("an interpolation in the string is never closed".to_owned()).into());
+				},
+				Err(e) => return Err(e),
+			}
+
+			text_start = parser.current_span().end - span.start - quotes;
+
+			match strings::find_interpolation(slice, text_start) {
+				Some(e) => open = e,
+				None => break,
+			}
+		}
+
+		template.push_str(&contents[text_start..]);
+
+		if quotes == 3 {
+			template = strings::dedent(&template);
+		}
+
+		let mut exprs = exprs.into_iter();
+		let mut parts: Vec<Expr> = Vec::new();
+
+		for (i, text) in template.split(strings::PLACEHOLDER).enumerate() {
+			if i > 0 {
+				parts.extend(exprs.next());
+			}
+
+			let text = strings::unescape(text).map_err(lex_error)?;
+
+			if !text.is_empty() {
+				parts.push(Expr::from(text));
+			}
+		}
+
+		Ok(Expr::Interpolation(parts))
+	}
+}
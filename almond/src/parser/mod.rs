@@ -2,79 +2,318 @@ use std::ops::Range;
 
 use logos::Logos;
 
-use crate::lexer::tokens::TokenKind;
+use crate::lexer::{Token, tokens::{TokenKind, describe_error}};
 
-use self::ast::Store;
+use self::{ast::{Store, Location}, cst::{Builder, SyntaxNode}, modules::ModuleResolver, errors::{SyntaxError, LexError, Error}};
 
 pub mod ast;
+pub mod cst;
+pub mod diagnostics;
 pub mod expressions;
+mod lower;
 pub mod errors;
+pub mod modules;
+mod strings;
 
 #[derive(Debug)]
 pub struct Parser<'a> {
 	lexer: logos::Lexer<'a, TokenKind>,
 	current: Option<TokenKind>,
+	current_span: Range<usize>,
 	slice: &'a str,
-	next: Option<TokenKind>
+	next: Option<TokenKind>,
+	/// names bound with `:=` in each enclosing block, innermost last
+	locals: Vec<Vec<String>>,
+	resolver: Option<&'a dyn ModuleResolver>,
+	/// the id of the file being parsed, if it came from a resolver
+	file: Option<String>,
+	/// the ids of every file currently being parsed, to catch circular imports
+	loading: Vec<String>,
+	/// the lines of the `///` comments just before the next token
+	docs: Vec<String>,
+	/// the documentation of the statement being parsed, for every binding it makes
+	statement_docs: Option<String>,
+	/// whether there's a line break before `next`, which can end a statement
+	newline: bool,
+	/// every token consumed so far, as a syntax tree
+	cst: Builder<'a>,
 }
 
 impl<'a> Parser<'a> {
 	pub fn new(input: &'a str) -> Parser<'a> {
-		let mut lexer = TokenKind::lexer(input);
+		let lexer = TokenKind::lexer(input);
 		let current = None;
+		let current_span = lexer.span();
 		let slice = lexer.slice();
-		let next = lexer.next();
+		let next = None;
+		let locals = Vec::new();
+		let resolver = None;
+		let file = None;
+		let loading = Vec::new();
+		let docs = Vec::new();
+		let statement_docs = None;
+		let newline = false;
+		let cst = Builder::default();
 
-		Parser { lexer, current, slice, next }
+		let mut parser = Parser { lexer, current, current_span, slice, next, locals, resolver, file, loading, docs, statement_docs, newline, cst };
+		parser.next = parser.advance();
+
+		parser
+	}
+
+	pub fn with_resolver(input: &'a str, resolver: &'a dyn ModuleResolver) -> Parser<'a> {
+		let mut parser = Parser::new(input);
+		parser.resolver = Some(resolver);
+
+		parser
+	}
+
+	/// a parser for a file loaded through `resolver`, imported by every file in `loading`
+	pub(crate) fn module(input: &'a str, resolver: &'a dyn ModuleResolver, file: String, mut loading: Vec<String>) -> Parser<'a> {
+		let mut parser = Parser::with_resolver(input, resolver);
+		loading.push(file.clone());
+
+		parser.file = Some(file);
+		parser.loading = loading;
+
+		parser
+	}
+
+	pub fn parse(&mut self) -> Result<Store, Error> {
+		let mut output = Store::new();
+
+		self.parse_input(&mut output)?;
+
+		Ok(output)
 	}
 
-	pub fn parse(&mut self) -> Store {
+	/// parses as much as it can, returning everything that parsed along with every error
+	pub fn parse_all(&mut self) -> (Store, Vec<Error>) {
 		let mut output = Store::new();
+		let errors = self.parse_input_recovering(&mut output);
 
-		self.parse_input(&mut output);
+		(output, errors)
+	}
 
-		output
+	/// the syntax tree of everything parsed so far, which is the whole source after `parse_all`
+	pub fn syntax_tree(self) -> SyntaxNode<'a> {
+		self.cst.finish()
 	}
 
 	pub(crate) fn peek(&self) -> Option<TokenKind> {
 		self.next
 	}
 
+	/// looks one token past `peek`
+	pub(crate) fn peek_second(&self) -> Option<TokenKind> {
+		self.lexer.clone().find(|e| !e.is_trivia())
+	}
+
 	pub(crate) fn next(&mut self) -> Option<TokenKind> {
 		self.current = self.next;
+		self.current_span = self.lexer.span();
 		self.slice = self.lexer.slice();
-		self.next = self.lexer.next();
+
+		if let Some(kind) = self.current {
+			self.cst.token(Token { kind, span: self.current_span(), slice: self.slice });
+		}
+
+		// `take_docs` has already taken the doc comments before a statement, so any left are inside of one
+		self.docs.clear();
+
+		self.next = self.advance();
 
 		self.current
 	}
 
+	/// lexes the token after the current one, skipping comments but keeping the text of doc comments
+	fn advance(&mut self) -> Option<TokenKind> {
+		let end = self.lexer.span().end;
+
+		let next = loop {
+			let kind = match self.lexer.next() {
+				Some(e) if e.is_trivia() => e,
+				e => break e,
+			};
+
+			let slice = self.lexer.slice();
+
+			// one at the end of a line with code on it is about that code, not the statement after it
+			if kind == TokenKind::DocComment && self.starts_line(self.lexer.span().start) {
+				let text = &slice[3..];
+				self.docs.push(text.strip_prefix(' ').unwrap_or(text).to_owned());
+			}
+
+			self.cst.trivia(Token { kind, span: self.lexer.span(), slice });
+		};
+
+		self.newline = self.lexer.source()[end..self.lexer.span().start].contains('\n');
+
+		next
+	}
+
+	/// whether nothing but spaces comes before `offset` on its line
+	fn starts_line(&self, offset: usize) -> bool {
+		let before = &self.lexer.source()[..offset];
+
+		before[before.rfind('\n').map_or(0, |i| i + 1)..].trim().is_empty()
+	}
+
+	/// whether there's a line break between the current token and the next one
+	pub(crate) fn newline_before_peek(&self) -> bool {
+		self.newline
+	}
+
+	/// starts a new statement, taking the doc comments just before it as its documentation
+	pub(crate) fn take_docs(&mut self) {
+		self.statement_docs = match self.docs.is_empty() {
+			true => None,
+			false => Some(std::mem::take(&mut self.docs).join("\n")),
+		};
+	}
+
+	/// documents `name` with the doc comments of the current statement
+	pub(crate) fn document(&self, output: &mut Store, name: &str) {
+		if let Some(docs) = &self.statement_docs {
+			output.docs.insert(name.to_owned(), docs.clone());
+		}
+	}
+
 	pub(crate) fn slice(&self) -> &'a str {
 		self.slice
 	}
 
+	/// the slice of the token `peek` returns
+	pub(crate) fn peek_slice(&self) -> &'a str {
+		self.lexer.slice()
+	}
+
+	/// the span of the token `peek` returns
 	pub(crate) fn span(&self) -> Range<usize> {
 		self.lexer.span()
 	}
 
-	pub(crate) fn consume<T: AsRef<TokenKind>>(&mut self, expected: T) {
+	/// the span of the token `next` last returned
+	pub(crate) fn current_span(&self) -> Range<usize> {
+		self.current_span.clone()
+	}
+
+	/// the source text from `start` up to the end of the current token
+	pub(crate) fn source_since(&self, start: usize) -> &'a str {
+		&self.lexer.source()[start..self.current_span.end]
+	}
+
+	/// where the source from `start` up to the end of the current token is
+	pub(crate) fn location(&self, start: usize) -> Location {
+		Location { file: self.file.clone(), span: start..self.current_span.end }
+	}
+
+	/// consumes the next token if it's `expected`, and otherwise leaves it for `synchronize` to look at
+	pub(crate) fn consume<T: AsRef<TokenKind>>(&mut self, expected: T) -> Result<(), SyntaxError> {
 		let expected = expected.as_ref().to_owned();
 
-		let token = self.next().expect(&format!(
-			"Expected to consume `{}`, but there was no next token",
-			expected
-		));
+		if self.peek() != Some(expected) {
+			return Err(self.unexpected(&[expected]));
+		}
+
+		self.next();
+
+		Ok(())
+	}
+
+	/// consumes an identifier, returning its name
+	pub(crate) fn expect_ident(&mut self) -> Result<&'a str, SyntaxError> {
+		self.consume(TokenKind::Ident)?;
+
+		Ok(self.ident())
+	}
+
+	/// the name of the identifier `next` last returned, without the backticks around a quoted one like `` `content-type` ``
+	pub(crate) fn ident(&self) -> &'a str {
+		ident_name(self.slice())
+	}
+
+	/// a parser for the expression starting at `start` in the same source, like one inside of a string,
+	/// which sees the source as ending at `end`
+	pub(crate) fn embedded(&self, start: usize, end: usize) -> Parser<'a> {
+		let mut lexer = TokenKind::lexer(&self.lexer.source()[..end]);
+		lexer.bump(start);
+
+		let current = None;
+		let current_span = lexer.span();
+		let slice = lexer.slice();
+
+		let mut parser = Parser {
+			lexer,
+			current,
+			current_span,
+			slice,
+			next: None,
+			locals: self.locals.clone(),
+			resolver: self.resolver,
+			file: self.file.clone(),
+			loading: self.loading.clone(),
+			docs: Vec::new(),
+			statement_docs: None,
+			newline: false,
+			cst: Builder::default(),
+		};
+		parser.next = parser.advance();
+
+		parser
+	}
 
-		assert_eq!(
-			token,
-			expected,
-			"Expected to consume `{}`, but found `{}`",
-			expected,
-			token
-		);
+	/// the value of the string token `next` last returned
+	pub(crate) fn string(&self) -> Result<String, LexError> {
+		strings::unquote(self.slice()).map_err(|e| LexError::new(self.slice(), self.current_span(), e).with_file(self.file.clone()))
+	}
+
+	/// an error for the next token, which the lexer couldn't make sense of
+	pub(crate) fn lex_error(&self) -> LexError {
+		LexError::new(self.peek_slice(), self.span(), describe_error(self.peek_slice())).with_file(self.file.clone())
+	}
+
+	/// an error for the next token, which isn't any of `expected`
+	pub(crate) fn unexpected(&self, expected: &[TokenKind]) -> SyntaxError {
+		let found = self.peek().unwrap_or(TokenKind::EOF);
+
+		SyntaxError::new(found, self.span(), expected.to_vec()).with_file(self.file.clone())
 	}
 }
 
-pub fn eval<'a>(input: &'a str) -> Store {
+/// the name of an identifier written as `slice`, without the backticks around a quoted one
+pub(crate) fn ident_name(slice: &str) -> &str {
+	match slice.strip_prefix('`') {
+		Some(e) => &e[..e.len() - 1],
+		None => slice,
+	}
+}
+
+pub fn eval(input: &str) -> Result<Store, Error> {
 	let mut parser = Parser::new(input);
 	parser.parse()
+}
+
+/// like `eval`, but returns every error in `input` along with everything that did parse
+pub fn eval_all(input: &str) -> (Store, Vec<Error>) {
+	let mut parser = Parser::new(input);
+	parser.parse_all()
+}
+
+/// parses `input` into a syntax tree that keeps every token of it, along with every error in it
+/// statements that don't parse are kept as `SyntaxKind::Error` nodes
+pub fn syntax_tree(input: &str) -> (SyntaxNode<'_>, Vec<Error>) {
+	let mut parser = Parser::new(input);
+	let (_, errors) = parser.parse_all();
+
+	(parser.syntax_tree(), errors)
+}
+
+/// loads and parses the file at `path` through `resolver`, along with everything it imports
+pub fn eval_file(path: &str, resolver: &dyn ModuleResolver) -> Result<Store, Error> {
+	let id = resolver.resolve(path, None)?;
+	let source = resolver.load(&id)?;
+
+	let mut parser = Parser::module(&source, resolver, id, Vec::new());
+	parser.parse()
 }
\ No newline at end of file
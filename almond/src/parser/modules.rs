@@ -0,0 +1,239 @@
+use std::{collections::HashMap, fmt, fs, path::PathBuf};
+
+use super::errors::ResolveError;
+
+/// finds and loads the files named by `import "path" as name;` and `from "path" import name;`
+pub trait ModuleResolver: fmt::Debug {
+	/// turns a path as written in the file `from` into an id that's the same
+	/// every time the same module is imported
+	fn resolve(&self, path: &str, from: Option<&str>) -> Result<String, ResolveError>;
+
+	/// the source of a module returned by `resolve`
+	fn load(&self, id: &str) -> Result<String, ResolveError>;
+}
+
+/// joins `path` onto the directory of `from`, where a leading `/` starts from the root instead
+/// paths can't use `..` to leave the root
+fn join(path: &str, from: Option<&str>) -> Result<String, ResolveError> {
+	let mut parts: Vec<&str> = match from {
+		Some(from) if !path.starts_with('/') => from.split('/').collect(),
+		_ => Vec::new(),
+	};
+
+	// the importing file itself
+	parts.pop();
+
+	for part in path.split('/') {
+		match part {
+			"" | "." => {},
+			".." => {
+				if parts.pop().is_none() {
+					return Err(ResolveError::new(path, "the path leaves the module root"));
+				}
+			},
+			part => parts.push(part),
+		}
+	}
+
+	if parts.is_empty() {
+		return Err(ResolveError::new(path, "the path doesn't name a file"));
+	}
+
+	Ok(parts.join("/"))
+}
+
+/// resolves imports relative to the importing file, inside of the directory `root`
+#[derive(Debug, Clone)]
+pub struct FileResolver {
+	root: PathBuf,
+}
+
+impl FileResolver {
+	pub fn new<T: Into<PathBuf>>(root: T) -> FileResolver {
+		let root = root.into();
+
+		FileResolver { root }
+	}
+}
+
+impl ModuleResolver for FileResolver {
+	fn resolve(&self, path: &str, from: Option<&str>) -> Result<String, ResolveError> {
+		join(path, from)
+	}
+
+	fn load(&self, id: &str) -> Result<String, ResolveError> {
+		fs::read_to_string(self.root.join(id)).map_err(|e| ResolveError::new(id, e.to_string()))
+	}
+}
+
+/// resolves imports the same way as `FileResolver`, but from files held in memory
+#[derive(Debug, Clone, Default)]
+pub struct MemoryResolver {
+	files: HashMap<String, String>,
+}
+
+impl MemoryResolver {
+	pub fn new() -> MemoryResolver {
+		let files: HashMap<String, String> = HashMap::new();
+
+		MemoryResolver { files }
+	}
+
+	/// adds a file, returning the source it replaced
+	pub fn insert<T, E>(&mut self, path: T, source: E) -> Result<Option<String>, ResolveError>
+	where
+	T: AsRef<str>,
+	String: From<E> {
+		let id = join(path.as_ref(), None)?;
+
+		Ok(self.files.insert(id, source.into()))
+	}
+}
+
+impl ModuleResolver for MemoryResolver {
+	fn resolve(&self, path: &str, from: Option<&str>) -> Result<String, ResolveError> {
+		join(path, from)
+	}
+
+	fn load(&self, id: &str) -> Result<String, ResolveError> {
+		match self.files.get(id) {
+			Some(e) => Ok(e.to_owned()),
+			None => Err(ResolveError::new(id, "no such file")),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::{fs, env};
+
+	use crate::parser::{eval_file, ast::Literal};
+
+	use super::{MemoryResolver, FileResolver};
+
+	fn files(files: &[(&str, &str)]) -> MemoryResolver {
+		let mut resolver = MemoryResolver::new();
+
+		for (path, source) in files {
+			resolver.insert(path, *source).unwrap();
+		}
+
+		resolver
+	}
+
+	#[test]
+	fn namespace_import() {
+		let files = files(&[
+			("main.almond", r#"import "common.almond" as common; url = common.host + "/api";"#),
+			("common.almond", r#"export host = "localhost"; secret = "hidden";"#),
+		]);
+
+		let store = eval_file("main.almond", &files).unwrap();
+
+		assert_eq!(store.get("url", None), Literal::String("localhost/api".to_owned()));
+	}
+
+	#[test]
+	fn from_import() {
+		let files = files(&[
+			("main.almond", r#"from "db.almond" import host, port as db_port; url = db_port + 1;"#),
+			("db.almond", r#"export host = "db"; export port = 5432;"#),
+		]);
+
+		let store = eval_file("main.almond", &files).unwrap();
+
+		assert_eq!(store.get("host", None), Literal::String("db".to_owned()));
+		assert_eq!(store.get("url", None), Literal::Int(5433));
+	}
+
+	#[test]
+	fn module_errors() {
+		let files = files(&[
+			("main.almond", r#"import "lib.almond" as lib; good = lib.good; bad = lib.bad; export good;"#),
+			("lib.almond", r#"import port: int = "x"; export good = 1; export bad = port + 1;"#),
+		]);
+
+		let store = eval_file("main.almond", &files).unwrap();
+
+		assert_eq!(store.get("good", None), Literal::Int(1));
+		assert!(matches!(store.get("bad", None), Literal::None));
+		assert_eq!(store.try_get("bad").unwrap_err().code(), "A0004");
+		assert_eq!(store.evaluate_outputs().unwrap_err().code(), "A0004");
+	}
+
+	#[test]
+	fn relative_paths() {
+		let files = files(&[
+			("envs/prod.almond", r#"from "../shared/db.almond" import host; export url = host;"#),
+			("shared/db.almond", r#"from "./names.almond" import name; export host = name;"#),
+			("shared/names.almond", r#"export name = "db";"#),
+		]);
+
+		let store = eval_file("envs/prod.almond", &files).unwrap();
+
+		assert_eq!(store.get("url", None), Literal::String("db".to_owned()));
+	}
+
+	#[test]
+	fn root_escape() {
+		let files = files(&[
+			("envs/prod.almond", r#"import "../../secret.almond" as secret;"#),
+		]);
+
+		let err = eval_file("envs/prod.almond", &files).unwrap_err();
+
+		assert!(err.to_string().contains("Couldn't import `../../secret.almond`: the path leaves the module root"));
+	}
+
+	#[test]
+	fn file_resolver() {
+		let root = env::temp_dir().join(format!("almond-modules-{}", std::process::id()));
+		fs::create_dir_all(root.join("shared")).unwrap();
+		fs::write(root.join("main.almond"), r#"import "shared/db.almond" as db; port = db.port;"#).unwrap();
+		fs::write(root.join("shared/db.almond"), "export port = 5432;").unwrap();
+
+		let store = eval_file("main.almond", &FileResolver::new(&root)).unwrap();
+		fs::remove_dir_all(&root).unwrap();
+
+		assert_eq!(store.get("port", None), Literal::Int(5432));
+	}
+
+	#[test]
+	fn from_import_unexported() {
+		let files = files(&[
+			("main.almond", r#"from "db.almond" import secret;"#),
+			("db.almond", r#"secret = "hidden";"#),
+		]);
+
+		let err = eval_file("main.almond", &files).unwrap_err();
+
+		assert!(err.to_string().contains("`secret` does not exist in scope `db.almond`"));
+	}
+
+	#[test]
+	fn circular_import() {
+		let files = files(&[
+			("main.almond", r#"import "a.almond" as a;"#),
+			("a.almond", r#"import "main.almond" as main;"#),
+		]);
+
+		let err = eval_file("main.almond", &files).unwrap_err();
+
+		assert!(err.to_string().contains("circular import `main.almond` -> `a.almond` -> `main.almond`"));
+	}
+
+	#[test]
+	fn spans_name_files() {
+		let files = files(&[
+			("main.almond", r#"import "db.almond" as db;"#),
+			("db.almond", r#"export port = 5432;"#),
+		]);
+
+		let store = eval_file("main.almond", &files).unwrap();
+		let port = store.modules["db.almond"].contents["port"].location().unwrap();
+
+		assert_eq!(port.file.as_deref(), Some("db.almond"));
+		assert_eq!(port.span, 14..18);
+		assert_eq!(store.locations["db"].file.as_deref(), Some("main.almond"));
+	}
+}
@@ -0,0 +1,210 @@
+/// the value of a string literal as it was written, with its quotes removed and escapes decoded
+/// raw strings like `r#"C:\path"#` are kept as they are, and triple-quoted strings have their indentation removed
+pub(crate) fn unquote(slice: &str) -> Result<String, String> {
+	if let Some(raw) = slice.strip_prefix('r') {
+		let hashes = raw.len() - raw.trim_start_matches('#').len();
+
+		return Ok(raw[hashes + 1..raw.len() - hashes - 1].to_owned());
+	}
+
+	if quotes(slice) == 3 {
+		return unescape(&dedent(&slice[3..slice.len() - 3]));
+	}
+
+	unescape(&slice[1..slice.len() - 1])
+}
+
+/// stands in for each interpolation while the indentation is removed from a multi-line string
+pub(crate) const PLACEHOLDER: char = '\0';
+
+/// how many quotes a string literal starts and ends with
+pub(crate) fn quotes(slice: &str) -> usize {
+	match slice.len() >= 6 && slice.starts_with(r#"""""#) {
+		true => 3,
+		false => 1,
+	}
+}
+
+/// the byte offset of the `{` starting the first interpolation in the contents of a string after `from`,
+/// if the string can have any
+/// only strings in double quotes can, so raw strings and ones in single quotes are kept as they're written
+pub(crate) fn find_interpolation(slice: &str, from: usize) -> Option<usize> {
+	if slice.starts_with('r') || slice.starts_with('\'') {
+		return None;
+	}
+
+	let quotes = quotes(slice);
+	let contents = &slice[quotes..slice.len() - quotes];
+	let mut chars = contents[from..].char_indices();
+
+	while let Some((i, e)) = chars.next() {
+		match e {
+			// the braces of a unicode escape aren't an interpolation
+			'\\' => {
+				if let Some((_, 'u')) = chars.next() {
+					chars.by_ref().find(|(_, e)| *e == '}');
+				}
+			},
+			'{' => return Some(from + i),
+			_ => {},
+		}
+	}
+
+	None
+}
+
+/// decodes `\n`, `\t`, `\r`, `\0`, `\\`, `\"`, `\'`, `\{`, `\}` and `\u{1F600}`
+pub(crate) fn unescape(text: &str) -> Result<String, String> {
+	let mut out = String::with_capacity(text.len());
+	let mut chars = text.chars();
+
+	while let Some(e) = chars.next() {
+		if e != '\\' {
+			out.push(e);
+			continue;
+		}
+
+		let escaped = match chars.next() {
+			Some('n') => '\n',
+			Some('t') => '\t',
+			Some('r') => '\r',
+			Some('0') => '\0',
+			Some(e @ ('\\' | '"' | '\'' | '{' | '}')) => e,
+			Some('u') => {
+				let rest = chars.as_str();
+
+				let code = match (rest.strip_prefix('{'), rest.find('}')) {
+					(Some(_), Some(end)) => &rest[1..end],
+					_ => return Err("unicode escapes look like `\\u{1F600}`".to_owned()),
+				};
+
+				let value = u32::from_str_radix(code, 16).ok().and_then(char::from_u32);
+
+				match value {
+					Some(e) => {
+						chars = rest[code.len() + 2..].chars();
+						e
+					},
+					None => return Err(format!("`{}` isn't a unicode character", code)),
+				}
+			},
+			Some(e) => return Err(format!("unknown escape `\\{}`", e)),
+			None => return Err("the string ends with a `\\`".to_owned()),
+		};
+
+		out.push(escaped);
+	}
+
+	Ok(out)
+}
+
+/// removes the line breaks just inside of the quotes of a multi-line string,
+/// and the indentation every line has in common
+pub(crate) fn dedent(text: &str) -> String {
+	let text = text.strip_prefix('\n').or_else(|| text.strip_prefix("\r\n")).unwrap_or(text);
+
+	// the indentation of the closing quotes doesn't count as part of the string
+	let text = match text.rfind('\n') {
+		Some(i) if text[i + 1..].trim().is_empty() => &text[..i],
+		_ => text,
+	};
+
+	let indent = text.lines()
+		.filter(|line| !line.trim().is_empty())
+		.map(|line| line.len() - line.trim_start().len())
+		.min()
+		.unwrap_or(0);
+
+	text.lines()
+		.map(|line| line.get(indent..).unwrap_or_else(|| line.trim_start()))
+		.collect::<Vec<&str>>()
+		.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::parser::{eval, ast::Literal};
+
+	use super::unquote;
+
+	#[test]
+	fn escapes() {
+		assert_eq!(unquote(r#""a\n\tb""#).unwrap(), "a\n\tb");
+		assert_eq!(unquote(r#""say \"hi\" \\ bye""#).unwrap(), r#"say "hi" \ bye"#);
+		assert_eq!(unquote(r#"'it\'s'"#).unwrap(), "it's");
+		assert_eq!(unquote(r#""\u{1F600}!""#).unwrap(), "😀!");
+
+		assert_eq!(unquote(r#""\q""#).unwrap_err(), "unknown escape `\\q`");
+		assert_eq!(unquote(r#""\u{110000}""#).unwrap_err(), "`110000` isn't a unicode character");
+	}
+
+	#[test]
+	fn raw_strings() {
+		assert_eq!(unquote(r#"r"C:\path\n""#).unwrap(), r"C:\path\n");
+		assert_eq!(unquote(r##"r#"say "hi""#"##).unwrap(), r#"say "hi""#);
+	}
+
+	#[test]
+	fn multi_line() {
+		let store = eval("
+			query = \"\"\"
+				SELECT *
+				  FROM users
+				WHERE name = \"admin\"
+			\"\"\";
+			path = r#\"C:\\new\"#;
+			quote = \"\\\"\";
+		").unwrap();
+
+		assert_eq!(store.get("query", None), Literal::String("SELECT *\n  FROM users\nWHERE name = \"admin\"".to_owned()));
+		assert_eq!(store.get("path", None), Literal::String("C:\\new".to_owned()));
+		assert_eq!(store.get("quote", None), Literal::String("\"".to_owned()));
+	}
+
+	#[test]
+	fn multi_line_interpolation() {
+		let store = eval("
+			host = \"db\";
+			port = 5432;
+			config = \"\"\"
+				[database]
+				  url = \"{host}:{port +
+					1}\"
+				{ \"braces\" }
+			\"\"\";
+		").unwrap();
+
+		assert_eq!(store.get("config", None), Literal::String("[database]\n  url = \"db:5433\"\nbraces".to_owned()));
+	}
+
+	#[test]
+	fn interpolation_errors() {
+		let err = eval(r#"a = "{";"#).unwrap_err();
+		assert!(err.to_string().ends_with("an interpolation in the string is never closed"), "{}", err);
+
+		let err = eval(r#"a = "{x"; b = "}";"#).unwrap_err();
+		assert!(err.to_string().starts_with("LexError: Invalid token `\"{x\"` at char `4`"), "{}", err);
+
+		let err = eval(r#"a = "{x y}";"#).unwrap_err();
+		assert_eq!(err.to_string(), "SyntaxError: Unexpected token `identifier` at char `8`, expected `}`");
+	}
+
+	#[test]
+	fn single_quotes() {
+		let store = eval(r#"json = '{"k": 1}'; name = "x"; both = '{name}' + "{name}";"#).unwrap();
+
+		assert_eq!(store.get("json", None), Literal::String(r#"{"k": 1}"#.to_owned()));
+		assert_eq!(store.get("both", None), Literal::String("{name}x".to_owned()));
+	}
+
+	#[test]
+	fn unterminated() {
+		let err = eval("nice = \"hello;\ncool = 5;").unwrap_err();
+
+		assert_eq!(err.code(), "A0009");
+		assert!(err.to_string().contains("at char `7`: the string is never closed"), "{}", err);
+
+		let err = eval(r#"nice = "\q";"#).unwrap_err();
+		assert!(err.to_string().ends_with("unknown escape `\\q`"), "{}", err);
+	}
+}